@@ -14,7 +14,7 @@
 ///! forks of logterm customise the files in src/custom
 #[path = "../custom/mod.rs"]
 pub mod custom;
-use self::custom::app::{set_main_view, App, DashViewMain};
+use self::custom::app::{back_to_previous_view, set_main_view, App, DashViewMain, HealthCriterion};
 use self::custom::ui::draw_dashboard;
 
 #[macro_use]
@@ -26,7 +26,7 @@ extern crate env_logger;
 pub mod shared;
 
 use crossterm::{
-	event::{self, DisableMouseCapture, EnableMouseCapture, Event as CEvent, KeyCode},
+	event::{self, DisableMouseCapture, EnableMouseCapture, Event as CEvent, KeyCode, KeyModifiers},
 	execute,
 	terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -34,6 +34,10 @@ use crossterm::{
 use std::{
 	error::Error,
 	io::{stdout, Write},
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc,
+	},
 	thread,
 	time::{Duration, Instant,SystemTime, UNIX_EPOCH},
 };
@@ -67,13 +71,24 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
 		Err(_e) => return Ok(()),
 	};
 
+	if let Some(line_rate) = app.config.stress_test {
+		let duration = chrono::Duration::seconds(app.config.stress_duration);
+		println!("{}", app.stress_test_mode(line_rate, duration).await);
+		return Ok(());
+	}
+
+	if app.is_streaming() {
+		app.stream_entries().await?;
+		return Ok(());
+	}
+
 	// Terminal initialization
 	enable_raw_mode()?;
 	let mut stdout = stdout();
 	execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
 	let backend = CrosstermBackend::new(stdout);
 	let mut terminal = Terminal::new(backend)?;
-	let mut rx = initialise_events(app.opt.tick_rate);
+	let mut rx = initialise_events(app.tick_rate_ms.clone());
 	terminal.clear()?;
 
 	// Use futures of async functions to handle events
@@ -83,6 +98,10 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
 		.duration_since(UNIX_EPOCH)
 		.expect("Time went backwards");
 	let mut next_update = start - Duration::from_secs(2);
+	let mut remote_rx = app.remote_rx.take().expect("remote_rx already taken");
+	let mut export_rx = app.export_rx.take().expect("export_rx already taken");
+	let mut watchdog_rx = app.watchdog_rx.take().expect("watchdog_rx already taken");
+	let _watchdog = app.watchdog(chrono::Duration::seconds(app.config.watchdog_timeout));
 	loop {
 		if next_update < SystemTime::now()
 			.duration_since(UNIX_EPOCH)
@@ -93,13 +112,38 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
 
 		let logfiles_future = app.logfiles.next().fuse();
 		let events_future = rx.recv().fuse();
-		pin_mut!(logfiles_future, events_future);
+		let remote_future = remote_rx.recv().fuse();
+		let export_future = export_rx.recv().fuse();
+		let watchdog_future = watchdog_rx.recv().fuse();
+		pin_mut!(logfiles_future, events_future, remote_future, export_future, watchdog_future);
 
 		select! {
 			(e) = events_future => {
 			match e {
 				Some(Event::Input(event)) => {
+					app.dash_state.command_message = None;
 					match event.code {
+						// The ':' command line takes keys until Enter or Esc
+						KeyCode::Enter if app.dash_state.command_line.is_some() => app.run_command_line(),
+						KeyCode::Esc if app.dash_state.command_line.is_some() => app.dash_state.command_line = None,
+						KeyCode::Backspace if app.dash_state.command_line.is_some() => {
+							if let Some(command) = app.dash_state.command_line.as_mut() { command.pop(); }
+						},
+						KeyCode::Char(c) if app.dash_state.command_line.is_some() => {
+							if let Some(command) = app.dash_state.command_line.as_mut() { command.push(c); }
+						},
+						KeyCode::Char(':') => app.dash_state.command_line = Some(String::new()),
+						KeyCode::Char('/') => app.dash_state.command_line = Some(String::from("search ")),
+						KeyCode::Enter => app.open_search_result(),
+						KeyCode::Char('?') => app.dash_state.help_overlay = !app.dash_state.help_overlay,
+						KeyCode::Char('n')|
+						KeyCode::Char('N') => app.dash_state.inspector_mode = !app.dash_state.inspector_mode,
+						KeyCode::Char('c') if event.modifiers.contains(KeyModifiers::CONTROL) => app.copy_latest_error(),
+						KeyCode::Char('c')|
+						KeyCode::Char('C') => app.dash_state.column_mode = !app.dash_state.column_mode,
+						KeyCode::Char('b')|
+						KeyCode::Char('B') => app.dash_state.mini_map = !app.dash_state.mini_map,
+
 						// For debugging, ~ sends a line to the debug_window
 						KeyCode::Char('~') => app.dash_state._debug_window(format!("Event::Input({:#?})", event).as_str()),
 
@@ -112,6 +156,7 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
 								DisableMouseCapture
 							)?;
 							terminal.show_cursor()?;
+							app.shutdown();
 							break Ok(());
 						},
 						// KeyCode::Char('s')|
@@ -126,13 +171,35 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
 						KeyCode::Char('o')|
 						KeyCode::Char('O') => app.scale_timeline_down(),
 
+						KeyCode::Char('l')|
+						KeyCode::Char('L') => app.dash_state.next_layout_preset(),
+
+						KeyCode::Char('[') => app.select_section(false),
+						KeyCode::Char(']') => app.select_section(true),
+
+						KeyCode::Char('m')|
+						KeyCode::Char('M') => app.merge_all_monitors(),
+
 						KeyCode::Down => app.handle_arrow_down(),
 						KeyCode::Up => app.handle_arrow_up(),
 						KeyCode::Right|
 						KeyCode::Tab => app.change_focus_next(),
 						KeyCode::Left => app.change_focus_previous(),
 
+						KeyCode::Char('g') if event.modifiers.contains(KeyModifiers::CONTROL) => app.export_topology(),
+						KeyCode::Char('f') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+							app.dash_state.command_line = Some(String::from("count "))
+						},
 						KeyCode::Char('g') => set_main_view(DashViewMain::DashDebug, &mut app),
+						KeyCode::Char('a') if event.modifiers.contains(KeyModifiers::CONTROL) => app.archive_focused(),
+						KeyCode::Char('p') if event.modifiers.contains(KeyModifiers::CONTROL) => app.screenshot(),
+						KeyCode::Char('h')|
+						KeyCode::Char('H') => set_main_view(DashViewMain::DashHealth, &mut app),
+						KeyCode::Char('f')|
+						KeyCode::Char('F') => app.focus_monitor_by_health(HealthCriterion::WorstHealthScore),
+						KeyCode::Char('w')|
+						KeyCode::Char('W') => set_main_view(DashViewMain::DashNetwork, &mut app),
+						KeyCode::Esc => back_to_previous_view(&mut app),
 						_ => {}
 					};
 					terminal.draw(|f| draw_dashboard(f, &mut app));
@@ -141,6 +208,9 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
 				Some(Event::Tick) => {
 					app.update_timelines(Some(Utc::now()));
 					app.update_chunk_store_stats();
+					app.export_metrics();
+					app.apply_retention();
+					app.flush_tee();
 				// draw_dashboard(&mut f, &dash_state, &mut monitors).unwrap();
 				// draw_dashboard(f, &dash_state, &mut monitors)?;
 				}
@@ -157,17 +227,7 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
 					let source = String::from(source_str);
 					// app.dash_state._debug_window(format!("{}: {}", source, line.line()).as_str());
 
-					match app.get_monitor_for_file_path(&source) {
-						Some(monitor) => {
-							monitor.append_to_content(line.line())?;
-							if monitor.is_debug_dashboard_log {
-								app.dash_state._debug_window(line.line());
-							}
-						},
-						None => {
-							app.dash_state._debug_window(format!("NO MONITOR FOR: {}", source).as_str());
-						},
-					}
+					app.append_line(&source, line.line())?;
 				},
 				Some(Err(e)) => {
 					app.dash_state._debug_window(format!("logfile error: {:#?}", e).as_str());
@@ -179,20 +239,35 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
 				}
 		}
 			},
+
+			(remote_line) = remote_future => {
+				if let Some(remote_line) = remote_line {
+					app.append_line(&remote_line.source, &remote_line.line)?;
+				}
+			},
+
+			(_) = export_future => app.export_on_signal(),
+
+			(timeout) = watchdog_future => {
+				if let Some(timeout) = timeout {
+					app.check_watchdog(timeout);
+				}
+			},
 		}
 	}
 }
 type Rx = tokio::sync::mpsc::UnboundedReceiver<Event<crossterm::event::KeyEvent>>;
 
-fn initialise_events(tick_rate: u64) -> Rx {
-	let tick_rate = Duration::from_millis(tick_rate);
+fn initialise_events(tick_rate_ms: Arc<AtomicU64>) -> Rx {
 	let (tx, rx) = mpsc::unbounded_channel(); // Setup input handling
 
 	thread::spawn(move || {
 		let mut last_tick = Instant::now();
 		loop {
+			// Read each time round as ':set tick_rate_ms=..' can change it
+			let tick_rate = Duration::from_millis(tick_rate_ms.load(Ordering::Relaxed));
 			// poll for tick rate duration, if no events, sent tick event.
-			if event::poll(tick_rate - last_tick.elapsed()).unwrap() {
+			if event::poll(tick_rate.checked_sub(last_tick.elapsed()).unwrap_or_default()).unwrap() {
 				if let CEvent::Key(key) = event::read().unwrap() {
 					tx.send(Event::Input(key));
 				}
@@ -16,7 +16,7 @@ use std::io;
 ///! forks of logterm customise the files in src/custom
 #[path = "../custom/mod.rs"]
 pub mod custom;
-use self::custom::app::{set_main_view, App, DashViewMain};
+use self::custom::app::{back_to_previous_view, set_main_view, App, DashViewMain, HealthCriterion};
 use self::custom::ui::draw_dashboard;
 
 #[macro_use]
@@ -26,7 +26,7 @@ extern crate env_logger;
 ///! logtail and its forks share code in src/
 #[path = "../mod.rs"]
 pub mod shared;
-use shared::event::{Event, Events};
+use shared::event::{Config as EventsConfig, Event, Events};
 
 use termion::{event::Key, input::MouseTerminal, raw::IntoRawMode, screen::AlternateScreen};
 use tui::{
@@ -68,7 +68,18 @@ async fn terminal_main() -> std::io::Result<()> {
 		}
 	};
 
-	let mut events = Events::new();
+	if let Some(line_rate) = app.config.stress_test {
+		let duration = chrono::Duration::seconds(app.config.stress_duration);
+		println!("{}", app.stress_test_mode(line_rate, duration).await);
+		return Ok(());
+	}
+
+	if app.is_streaming() {
+		app.stream_entries().await?;
+		return Ok(());
+	}
+
+	let mut events = Events::with_tick_rate(EventsConfig::default(), app.tick_rate_ms.clone());
 
 	// Terminal initialization
 	info!("Intialising terminal (termion backend)");
@@ -86,6 +97,10 @@ async fn terminal_main() -> std::io::Result<()> {
 		.duration_since(UNIX_EPOCH)
 		.expect("Time went backwards");
 	let mut next_update = start - Duration::from_secs(2);
+	let mut remote_rx = app.remote_rx.take().expect("remote_rx already taken");
+	let mut export_rx = app.export_rx.take().expect("export_rx already taken");
+	let mut watchdog_rx = app.watchdog_rx.take().expect("watchdog_rx already taken");
+	let _watchdog = app.watchdog(chrono::Duration::seconds(app.config.watchdog_timeout));
 	loop {
 		if next_update < SystemTime::now()
 			.duration_since(UNIX_EPOCH)
@@ -96,18 +111,45 @@ async fn terminal_main() -> std::io::Result<()> {
 
 		let events_future = events.rx.recv().fuse();
 		let logfiles_future = app.logfiles.next().fuse();
-		pin_mut!(events_future, logfiles_future);
+		let remote_future = remote_rx.recv().fuse();
+		let export_future = export_rx.recv().fuse();
+		let watchdog_future = watchdog_rx.recv().fuse();
+		pin_mut!(events_future, logfiles_future, remote_future, export_future, watchdog_future);
 
 		select! {
 			(e) = events_future => {
 				match e {
 					Some(Event::Input(input)) => {
+						app.dash_state.command_message = None;
 						match input {
+							// The ':' command line takes keys until Enter or Esc
+							Key::Char('\n') if app.dash_state.command_line.is_some() => app.run_command_line(),
+							Key::Esc if app.dash_state.command_line.is_some() => app.dash_state.command_line = None,
+							Key::Backspace if app.dash_state.command_line.is_some() => {
+								if let Some(command) = app.dash_state.command_line.as_mut() { command.pop(); }
+							},
+							Key::Char(c) if app.dash_state.command_line.is_some() => {
+								if let Some(command) = app.dash_state.command_line.as_mut() { command.push(c); }
+							},
+							Key::Char(':') => app.dash_state.command_line = Some(String::new()),
+							Key::Char('/') => app.dash_state.command_line = Some(String::from("search ")),
+							Key::Char('\n') => app.open_search_result(),
+							Key::Char('?') => app.dash_state.help_overlay = !app.dash_state.help_overlay,
+							Key::Char('n')|
+							Key::Char('N') => app.dash_state.inspector_mode = !app.dash_state.inspector_mode,
+							Key::Char('c')|
+							Key::Char('C') => app.dash_state.column_mode = !app.dash_state.column_mode,
+							Key::Char('b')|
+							Key::Char('B') => app.dash_state.mini_map = !app.dash_state.mini_map,
+
 							// For debugging, ~ sends a line to the debug_window
 							Key::Char('~') => app.dash_state._debug_window(format!("Event::Input({:#?})", input).as_str()),
 
 							Key::Char('q')|
-							Key::Char('Q') => return Ok(()),
+							Key::Char('Q') => {
+								app.shutdown();
+								return Ok(());
+							},
 						// Key::Char('s')|
 						// Key::Char('S') => app.set_main_view(DashViewMain::DashSummary),
 							Key::Char('v')|
@@ -119,6 +161,15 @@ async fn terminal_main() -> std::io::Result<()> {
 							Key::Char('-')|
 							Key::Char('o')|
 							Key::Char('O') => app.scale_timeline_down(),
+
+							Key::Char('l')|
+							Key::Char('L') => app.dash_state.next_layout_preset(),
+
+							Key::Char('[') => app.select_section(false),
+							Key::Char(']') => app.select_section(true),
+
+							Key::Char('m')|
+							Key::Char('M') => app.merge_all_monitors(),
 	
 							Key::Down => app.handle_arrow_down(),
 							Key::Up => app.handle_arrow_up(),
@@ -127,6 +178,18 @@ async fn terminal_main() -> std::io::Result<()> {
 							Key::Left => app.change_focus_previous(),
 
 							Key::Char('g') => set_main_view(DashViewMain::DashDebug, &mut app),
+							Key::Ctrl('a') => app.archive_focused(),
+							Key::Ctrl('p') => app.screenshot(),
+							Key::Ctrl('c') => app.copy_latest_error(),
+							Key::Ctrl('g') => app.export_topology(),
+							Key::Ctrl('f') => app.dash_state.command_line = Some(String::from("count ")),
+							Key::Char('h')|
+							Key::Char('H') => set_main_view(DashViewMain::DashHealth, &mut app),
+							Key::Char('f')|
+							Key::Char('F') => app.focus_monitor_by_health(HealthCriterion::WorstHealthScore),
+							Key::Char('w')|
+							Key::Char('W') => set_main_view(DashViewMain::DashNetwork, &mut app),
+							Key::Esc => back_to_previous_view(&mut app),
 								_ => {},
 						};
 						match terminal.draw(|f| draw_dashboard(f, &mut app)) {
@@ -142,6 +205,9 @@ async fn terminal_main() -> std::io::Result<()> {
 						trace!("Event::Tick");
 						app.update_timelines(Some(Utc::now()));
 						app.update_chunk_store_stats();
+						app.export_metrics();
+						app.apply_retention();
+						app.flush_tee();
 						match terminal.draw(|f| draw_dashboard(f, &mut app)) {
 							Ok(_) => {},
 							Err(e) => {
@@ -163,16 +229,8 @@ async fn terminal_main() -> std::io::Result<()> {
 						let source = String::from(source_str);
 						// app.dash_state._debug_window(format!("{}: {}", source, line.line()).as_str());
 
-						match app.get_monitor_for_file_path(&source) {
-							Some(monitor) => {
-								trace!("APPENDING: {}", line.line());
-								monitor.append_to_content(line.line())?;
-								if monitor.is_debug_dashboard_log {
-									app.dash_state._debug_window(line.line());
-								}
-							},
-							None => (),
-						}
+						trace!("APPENDING: {}", line.line());
+						app.append_line(&source, line.line())?;
 					},
 					Some(Err(e)) => {
 						app.dash_state._debug_window(format!("logfile error: {:#?}", e).as_str());
@@ -185,6 +243,19 @@ async fn terminal_main() -> std::io::Result<()> {
 					}
 				}
 			},
+			(remote_line) = remote_future => {
+				if let Some(remote_line) = remote_line {
+					app.append_line(&remote_line.source, &remote_line.line)?;
+				}
+			},
+
+			(_) = export_future => app.export_on_signal(),
+
+			(timeout) = watchdog_future => {
+				if let Some(timeout) = timeout {
+					app.check_watchdog(timeout);
+				}
+			},
 		}
 	}
 }
@@ -6,15 +6,33 @@ use std::collections::HashMap;
 
 use chrono::{DateTime, Duration, Utc};
 use std::fs::{File, OpenOptions};
-use std::io::{Read, Error, ErrorKind, Write};
+use std::io::{BufWriter, Read, Error, ErrorKind, Write};
 use std::path::{Path, PathBuf};
 use structopt::StructOpt;
 use tempfile::NamedTempFile;
-
-use crate::custom::opt::{Opt, MIN_TIMELINE_STEPS};
+use tui::backend::TestBackend;
+use tui::layout::Rect;
+use tui::style::{Color, Modifier, Style};
+use tui::Terminal;
+
+use crate::custom::format_loader::{load_format, set_custom_format, with_custom_format, LogFormat};
+use crate::custom::metrics_server::{serve_metrics, MetricsExporter};
+use crate::custom::config::Config;
+use crate::custom::opt::{Opt, OptError, OutputFormat, MIN_TIMELINE_STEPS};
+use crate::custom::snapshot::MetricsSnapshot;
+#[cfg(feature = "web-ui")]
+use crate::custom::web_ui::{serve_web_ui, SharedSnapshots};
+use crate::custom::remote::{remote_monitor_name, stream_lines, RemoteLine, REMOTE_CHANNEL_SIZE};
+use crate::custom::ui::{buffer_to_ansi, draw_dashboard};
+use rayon::prelude::*;
+use std::net::SocketAddr;
+use tokio::stream::StreamExt;
+use tokio::sync::mpsc;
 use crate::shared::util::StatefulList;
 
 pub static DEBUG_WINDOW_NAME: &str = "Debug Window";
+pub static AGGREGATE_MONITOR_NAME: &str = "All Nodes";
+static RETENTION_TICKS: u64 = 60; // Ticks between applying --retention
 
 use std::sync::Mutex;
 lazy_static::lazy_static! {
@@ -47,71 +65,100 @@ pub unsafe fn debug_log(message: &str) {
 }
 
 pub struct App {
-	pub opt: Opt,
+	pub config: Config,
 	pub dash_state: DashState,
 	pub monitors: HashMap<String, LogMonitor>,
 	pub logfile_with_focus: String,
 	pub logfiles: MuxedLines,
 	pub logfile_names: Vec<String>,
+	pub metrics_exporter: Option<MetricsExporter>,
+	#[cfg(feature = "web-ui")]
+	web_ui_snapshots: Option<SharedSnapshots>, // Served by --web-ui-port
+	remote_tx: mpsc::Sender<RemoteLine>,
+	pub remote_rx: Option<mpsc::Receiver<RemoteLine>>, // Taken by the event loop
+	pub export_rx: Option<mpsc::Receiver<()>>, // SIGUSR1, taken by the event loop
+	watchdog_tx: mpsc::Sender<Duration>,
+	pub watchdog_rx: Option<mpsc::Receiver<Duration>>, // See App::watchdog(), taken by the event loop
+	retention_ticks: u64, // See apply_retention()
+	pub pending_writes: PendingWrites, // See App::shutdown()
+	pub tick_rate_ms: std::sync::Arc<AtomicU64>, // Read by the event loop so set_opt() can change it
+	tee: Option<BufWriter<File>>, // See --tee
 }
 
 impl App {
 	pub async fn new() -> Result<App, std::io::Error> {
-		let mut opt = Opt::from_args();
-
-		if opt.files.is_empty() {
-			println!("{}: no logfile(s) specified.", Opt::clap().get_name());
-			return exit_with_usage("missing logfiles");
-		}
-
-		if opt.timeline_steps < MIN_TIMELINE_STEPS {
-			println!(
-				"Timeline steps number is too small, minimum is {}",
-				MIN_TIMELINE_STEPS
-			);
-			return exit_with_usage("invalid parameter");
+		let result = match Config::from_opt(Opt::from_args()) {
+			Ok(config) => App::new_from_config(config).await,
+			Err(e) => Err(e),
+		};
+		match result {
+			Err(e) if e.kind() == ErrorKind::InvalidInput => {
+				println!("{}: {}", Opt::clap().get_name(), e);
+				exit_with_usage("invalid parameter")
+			}
+			result => result,
 		}
+	}
 
+	///! Create an App without parsing the command line. Errors in the config
+	///! are returned with ErrorKind::InvalidInput, rather than printed.
+	pub async fn new_from_config(mut config: Config) -> Result<App, std::io::Error> {
 		let mut dash_state = DashState::new();
-		dash_state.debug_window = opt.debug_window;
-		if opt.debug_dashboard {
+		dash_state.debug_window = config.debug_window;
+		if config.debug_dashboard {
 			dash_state.main_view = DashViewMain::DashDebug;
 		}
+		if let Err(e) = dash_state.layout_preset(&config.layout_preset) {
+			return Err(Error::new(ErrorKind::InvalidInput, e.to_string()));
+		}
+
+		if let LogFormat::Custom(path) = &config.log_format {
+			match load_format(path) {
+				Ok(format) => set_custom_format(format),
+				Err(e) => return Err(Error::new(ErrorKind::InvalidInput, e)),
+			}
+		}
 
 		let mut monitors: HashMap<String, LogMonitor> = HashMap::new();
 		let mut logfiles = MuxedLines::new()?;
 		let mut debug_logfile_name = String::new();
 		let mut logfile_names = Vec::<String>::new();
 
-		let mut debug_logfile: Option<tempfile::NamedTempFile> = if opt.debug_window {
-			opt.files = opt.files[0..1].to_vec();
+		let mut debug_logfile: Option<tempfile::NamedTempFile> = if config.debug_window {
+			config.files.truncate(1); // May be empty with --remote or --stress-test
 			let named_file = NamedTempFile::new()?;
 			let path = named_file.path();
 			let path_str = path
 				.to_str()
 				.ok_or_else(|| Error::new(ErrorKind::Other, "invalid path"))?;
-			opt.files.push(String::from(path_str));
+			config.files.push(String::from(path_str));
 			debug_logfile_name = String::from(path_str);
 			Some(named_file)
 		} else {
 			None
 		};
 
-		println!("Loading {} files...", opt.files.len());
+		println!("Loading {} files...", config.files.len());
 		let mut first_logfile = String::new();
-		for f in &opt.files {
+		for f in &config.files {
+			if *f != debug_logfile_name && logfile_names.len() >= config.max_monitors {
+				return Err(Error::new(
+					ErrorKind::InvalidInput,
+					format!("max monitors reached, use --max-monitors to allow more than {}", config.max_monitors),
+				));
+			}
 			println!("file: {}", f);
 			if first_logfile.is_empty() {
 				first_logfile = f.to_string();
 			}
-			let mut monitor = LogMonitor::new(&opt, f.to_string(), opt.lines_max);
-			if opt.debug_window && monitor.index == 0 {
+			let mut monitor = LogMonitor::new(&config, f.to_string(), config.lines_max);
+			if config.debug_window && monitor.index == 0 {
 				if let Some(named_file) = debug_logfile {
 					*DEBUG_LOGFILE.lock().unwrap() = Some(named_file);
 					debug_logfile = None;
 				}
 			}
-			if opt.ignore_existing {
+			if config.ignore_existing {
 				logfile_names.push(f.to_string());
 				monitors.insert(f.to_string(), monitor);
 			} else {
@@ -139,15 +186,80 @@ impl App {
 			}
 		}
 
-		let activate_debug_dashboard = opt.debug_dashboard;
+		let metrics_exporter = match config.emit_metrics_port {
+			Some(port) => {
+				let exporter = MetricsExporter::new()?;
+				let registry = exporter.registry();
+				tokio::spawn(async move {
+					if let Err(e) = serve_metrics(port, registry).await {
+						debug_log!(format!("metrics server failed: {}", e).as_str());
+					}
+				});
+				Some(exporter)
+			}
+			None => None,
+		};
+
+		#[cfg(feature = "web-ui")]
+		let web_ui_snapshots = match config.web_ui_port {
+			Some(port) => {
+				let snapshots = SharedSnapshots::default();
+				let served_snapshots = snapshots.clone();
+				tokio::spawn(async move {
+					if let Err(e) = serve_web_ui(port, served_snapshots).await {
+						debug_log!(format!("web UI server failed: {}", e).as_str());
+					}
+				});
+				Some(snapshots)
+			}
+			None => None,
+		};
+
+		if let Some(delta) = config.time_offset {
+			for (_monitor_file, monitor) in monitors.iter_mut() {
+				monitor.metrics.shift_time(delta);
+			}
+		}
+
+		let activate_debug_dashboard = config.debug_dashboard;
+		let remote = config.remote;
+		let (remote_tx, remote_rx) = mpsc::channel(REMOTE_CHANNEL_SIZE);
+		let (export_tx, export_rx) = mpsc::channel(1);
+		tokio::spawn(forward_export_signals(export_tx));
+		let (watchdog_tx, watchdog_rx) = mpsc::channel(1);
+		let tick_rate = config.tick_rate;
 		let mut app = App {
-			opt,
+			config,
 			dash_state,
 			monitors,
 			logfile_with_focus: first_logfile.clone(),
 			logfiles,
 			logfile_names,
+			metrics_exporter,
+			#[cfg(feature = "web-ui")]
+			web_ui_snapshots,
+			remote_tx,
+			remote_rx: Some(remote_rx),
+			export_rx: Some(export_rx),
+			watchdog_tx,
+			watchdog_rx: Some(watchdog_rx),
+			retention_ticks: 0,
+			pending_writes: PendingWrites::default(),
+			tick_rate_ms: std::sync::Arc::new(AtomicU64::new(tick_rate)),
+			tee: None,
 		};
+		if let Some(path) = app.config.tee.clone() {
+			if let Err(e) = app.tee_to_file(&path) {
+				println!("ERROR: unable to open --tee file {}: {}", path.display(), e);
+				return Err(e);
+			}
+		}
+		if let Some(addr) = remote {
+			app.connect_to_remote(addr)?;
+			if first_logfile.is_empty() {
+				first_logfile = remote_monitor_name(&addr);
+			}
+		}
 		app.update_timelines(Some(Utc::now()));
 
 		if !first_logfile.is_empty() {
@@ -162,16 +274,673 @@ impl App {
 		Ok(app)
 	}
 
+	///! Change an option while running, such as from the ':' command line:
+	///!	'set lines_max=500'
+	///!	'set tick_rate_ms=100'
+	pub fn set_opt(&mut self, key: &str, value: &str) -> Result<(), OptError> {
+		let invalid_value = || OptError::InvalidValue(key.to_string(), value.to_string());
+		match key {
+			"lines_max" => {
+				let lines_max = value.parse::<usize>().map_err(|_| invalid_value())?;
+				if lines_max == 0 {
+					return Err(invalid_value());
+				}
+				self.config.lines_max = lines_max;
+				self.foreach_monitor(|monitor| monitor.set_max_content(lines_max));
+			}
+			"timeline_steps" => {
+				let timeline_steps = value.parse::<usize>().map_err(|_| invalid_value())?;
+				if timeline_steps < MIN_TIMELINE_STEPS {
+					return Err(invalid_value());
+				}
+				self.config.timeline_steps = timeline_steps;
+				self.foreach_monitor(|monitor| monitor.metrics.set_timeline_steps(timeline_steps));
+			}
+			"tick_rate" | "tick_rate_ms" => {
+				let tick_rate = value.parse::<u64>().map_err(|_| invalid_value())?;
+				if tick_rate == 0 {
+					return Err(invalid_value());
+				}
+				self.config.tick_rate = tick_rate;
+				self.tick_rate_ms.store(tick_rate, Ordering::Relaxed);
+			}
+			_ => return Err(OptError::UnknownKey(key.to_string())),
+		}
+		Ok(())
+	}
+
+	///! Move the content of the focused monitor to a gzipped file (see LogMonitor::archive())
+	pub fn archive_focused(&mut self) {
+		let message = match self.get_monitor_with_focus() {
+			Some(monitor) => {
+				let path = monitor.next_archive_path();
+				match monitor.archive(&path) {
+					Ok(()) => format!("archived to {}", path.display()),
+					Err(e) => format!("error: archive to {} failed: {}", path.display(), e),
+				}
+			}
+			None => return,
+		};
+		self.dash_state.command_message = Some(message);
+	}
+
+	///! Render the dashboard as it is now as ANSI text
+	pub fn screenshot_ansi(&mut self) -> std::io::Result<String> {
+		let size = self.dash_state.terminal_size.unwrap_or_else(|| Rect::new(0, 0, 80, 24));
+		let mut terminal = Terminal::new(TestBackend::new(size.width, size.height))?;
+		terminal.draw(|f| draw_dashboard(f, self))?;
+		Ok(buffer_to_ansi(terminal.backend().buffer()))
+	}
+
+	///! Render the dashboard as it is now and write it to path as ANSI text
+	pub fn save_screenshot(&mut self, path: &Path) -> std::io::Result<()> {
+		let screenshot = self.screenshot_ansi()?;
+		let mut file = File::create(path)?;
+		file.write_all(screenshot.as_bytes())
+	}
+
+	///! Save a screenshot named for the current time in the background (see PendingWrites)
+	pub fn screenshot(&mut self) {
+		let path = PathBuf::from(format!("vdash-{}.ans", Utc::now().format("%Y%m%d-%H%M%S")));
+		let message = match self.screenshot_ansi() {
+			Ok(screenshot) => {
+				let message = format!("saving screenshot to {}", path.display());
+				self.pending_writes.spawn(format!("screenshot to {}", path.display()), move || {
+					std::fs::write(&path, screenshot)
+				});
+				message
+			}
+			Err(e) => format!("error: screenshot to {} failed: {}", path.display(), e),
+		};
+		self.dash_state.command_message = Some(message);
+	}
+
+	///! Wait up to --graceful-shutdown-timeout for pending writes, before quitting
+	///!
+	///! Returns false, having logged a warning, if writes are still pending.
+	pub fn shutdown(&self) -> bool {
+		let timeout = std::time::Duration::from_secs(self.config.graceful_shutdown_timeout);
+		if self.pending_writes.wait(timeout) {
+			return true;
+		}
+		warn!(
+			"quitting with {} file write(s) unfinished after {}s",
+			self.pending_writes.count(),
+			self.config.graceful_shutdown_timeout
+		);
+		false
+	}
+
+	///! Copy the message of the latest error of the focused node to the clipboard (see --clipboard)
+	pub fn copy_latest_error(&mut self) {
+		if !self.config.clipboard {
+			self.dash_state.command_message = Some(String::from("use --clipboard to copy errors with ctrl-c"));
+			return;
+		}
+		let error = match self.get_monitor_with_focus() {
+			Some(monitor) => monitor.latest_error().map(String::from),
+			None => return,
+		};
+		let message = match error {
+			Some(error) => match copy_to_clipboard(&error) {
+				Ok(()) => {
+					self.dash_state._debug_window(&format!("copied: {}", error));
+					String::from("latest error copied to clipboard")
+				}
+				Err(e) => format!("error: copy to clipboard failed: {}", e),
+			},
+			None => String::from("no errors to copy"),
+		};
+		self.dash_state.command_message = Some(message);
+	}
+
+	///! Execute and close the ':' command line
+	pub fn run_command_line(&mut self) {
+		let command = match self.dash_state.command_line.take() {
+			Some(command) => command,
+			None => return,
+		};
+
+		let mut words = command.trim().splitn(2, ' ');
+		let result = match (words.next(), words.next()) {
+			(Some("set"), Some(setting)) => match setting.find('=') {
+				Some(equals) => self
+					.set_opt(setting[..equals].trim(), setting[equals + 1..].trim())
+					.map(|()| format!("{}", setting.trim()))
+					.map_err(|e| format!("{}", e)),
+				None => Err(String::from("usage: set key=value")),
+			},
+			(Some("search"), Some(pattern)) => {
+				self.search(pattern.trim());
+				return;
+			}
+			(Some("count"), Some(pattern)) => match self.get_monitor_with_focus() {
+				Some(monitor) => monitor
+					.grep_count(pattern.trim())
+					.map(|count| format!("Pattern matched {}/{} lines", count, monitor.content.items.len()))
+					.map_err(|e| format!("{}", e)),
+				None => return,
+			},
+			(Some(""), None) => return,
+			_ => Err(format!("unknown command '{}'", command.trim())),
+		};
+		self.dash_state.command_message = Some(match result {
+			Ok(message) => message,
+			Err(message) => format!("error: {}", message),
+		});
+	}
+
+	///! Find the lines of content matching pattern in every monitor, searching monitors in parallel
+	///!
+	///! The pattern is a regular expression, or plain text if it isn't a valid one.
+	///! Returns the matching line indices for each logfile with a match, in logfile_names order.
+	pub fn multi_search(&self, pattern: &str) -> Vec<(String, Vec<usize>)> {
+		let regex = Regex::new(pattern).or_else(|_| Regex::new(&regex::escape(pattern)));
+		let regex = match regex {
+			Ok(regex) => regex,
+			Err(_) => return Vec::new(),
+		};
+
+		let monitors: Vec<&LogMonitor> = self
+			.logfile_names
+			.iter()
+			.filter_map(|name| self.monitors.get(name))
+			.filter(|monitor| !monitor.is_debug_dashboard_log)
+			.collect();
+		monitors
+			.par_iter()
+			.map(|monitor| (monitor.logfile.clone(), monitor.search(&regex)))
+			.filter(|(_, indices)| !indices.is_empty())
+			.collect()
+	}
+
+	///! Show the results of multi_search() in the search view
+	pub fn search(&mut self, pattern: &str) {
+		self.dash_state.search_results = self.multi_search(pattern);
+		self.dash_state.search_pattern = pattern.to_string();
+		self.dash_state.search_selected = 0;
+		set_main_view(DashViewMain::DashSearch, self);
+	}
+
+	///! Show the selected search result in its node view
+	pub fn open_search_result(&mut self) {
+		if self.dash_state.main_view != DashViewMain::DashSearch {
+			return;
+		}
+		if let Some((logfile, index)) = self.dash_state.selected_search_result() {
+			if let Some(monitor) = self.monitors.get_mut(&logfile) {
+				monitor.content.state.select(Some(index));
+			}
+			self.dash_state.dash_node_focus = logfile;
+			set_main_view(DashViewMain::DashNode, self);
+		}
+	}
+
+	///! Current problems with all the nodes, most severe first
+	pub fn health_check(&self) -> Vec<HealthIssue> {
+		let mut issues = Vec::<HealthIssue>::new();
+		for logfile in self.logfile_names.iter() {
+			if let Some(monitor) = self.monitors.get(logfile) {
+				if !monitor.is_aggregate && !monitor.is_debug_dashboard_log {
+					issues.append(&mut monitor.health_issues());
+				}
+			}
+		}
+		issues.sort_by(|a, b| {
+			b.kind
+				.severity()
+				.partial_cmp(&a.kind.severity())
+				.unwrap_or(std::cmp::Ordering::Equal)
+		});
+		issues
+	}
+
+	///! Show the node view of the node which scores worst by criterion
+	pub fn focus_monitor_by_health(&mut self, criterion: HealthCriterion) {
+		let mut worst: Option<(&String, f64)> = None;
+		for logfile in self.logfile_names.iter() {
+			if let Some(monitor) = self.monitors.get(logfile) {
+				if monitor.is_aggregate || monitor.is_debug_dashboard_log {
+					continue;
+				}
+				if let Some(badness) = criterion.badness(&monitor.metrics) {
+					if worst.map_or(true, |(_, worst_badness)| badness > worst_badness) {
+						worst = Some((logfile, badness));
+					}
+				}
+			}
+		}
+
+		match worst.map(|(logfile, _)| logfile.clone()) {
+			Some(logfile) => {
+				self.dash_state.command_message = Some(format!("{}: {}", criterion.description(), logfile));
+				self.dash_state.dash_node_focus = logfile.clone();
+				if self.dash_state.main_view == DashViewMain::DashNode {
+					self.set_logfile_with_focus(logfile);
+				} else {
+					set_main_view(DashViewMain::DashNode, self);
+				}
+			}
+			None => {
+				self.dash_state.command_message = Some(format!("{}: no node found", criterion.description()));
+			}
+		}
+	}
+
+	///! The monitors of each node grouped by NodeAgebracket, in logfile_names order
+	pub fn partition_monitors_by_agebracket(&self) -> HashMap<NodeAgebracket, Vec<&LogMonitor>> {
+		let mut partition = HashMap::<NodeAgebracket, Vec<&LogMonitor>>::new();
+		for logfile in self.logfile_names.iter() {
+			if let Some(monitor) = self.monitors.get(logfile) {
+				if !monitor.is_aggregate && !monitor.is_debug_dashboard_log {
+					partition.entry(monitor.metrics.agebracket).or_insert_with(Vec::new).push(monitor);
+				}
+			}
+		}
+		partition
+	}
+
+	///! Apply f to every monitor, in logfile_names order
+	pub fn foreach_monitor<F: FnMut(&mut LogMonitor)>(&mut self, mut f: F) {
+		for name in self.logfile_names.iter() {
+			if let Some(monitor) = self.monitors.get_mut(name) {
+				f(monitor);
+			}
+		}
+	}
+
+	///! Apply f to every monitor, in logfile_names order, returning the first error
+	///!
+	///! Every monitor is visited even if f fails for an earlier one
+	pub fn foreach_monitor_result<F: FnMut(&mut LogMonitor) -> std::io::Result<()>>(
+		&mut self,
+		mut f: F,
+	) -> std::io::Result<()> {
+		let mut first_error = None;
+		self.foreach_monitor(|monitor| {
+			if let Err(e) = f(monitor) {
+				first_error.get_or_insert(e);
+			}
+		});
+		match first_error {
+			Some(e) => Err(e),
+			None => Ok(()),
+		}
+	}
+
 	pub fn update_timelines(&mut self, now: Option<DateTime<Utc>>) {
-		for (_monitor_file, monitor) in self.monitors.iter_mut() {
-			monitor.metrics.update_timelines(now);
+		self.foreach_monitor(|monitor| monitor.metrics.update_timelines(now));
+	}
+
+	///! Call on each tick to discard entries older than --retention every RETENTION_TICKS
+	pub fn apply_retention(&mut self) {
+		let retention = match self.config.retention {
+			Some(minutes) => Duration::minutes(minutes),
+			None => return,
+		};
+		self.retention_ticks += 1;
+		if self.retention_ticks % RETENTION_TICKS != 0 {
+			return;
+		}
+		self.foreach_monitor(|monitor| {
+			if let Some(most_recent) = monitor.metrics.most_recent {
+				monitor.truncate_before(most_recent - retention);
+			}
+		});
+	}
+
+	///! Serializable copies of the metrics of every monitored logfile, in logfile_names order
+	pub fn metrics_snapshots(&self) -> Vec<MetricsSnapshot> {
+		self.logfile_names
+			.iter()
+			.filter_map(|name| self.monitors.get(name))
+			.filter(|monitor| !monitor.is_aggregate && !monitor.is_debug_dashboard_log)
+			.map(MetricsSnapshot::from_monitor)
+			.collect()
+	}
+
+	///! True if logfile entries should be written to stdout rather than showing the
+	///! dashboard, which is when stdout is not a terminal unless --force-tui is given
+	pub fn is_streaming(&self) -> bool {
+		!self.config.force_tui && !atty::is(atty::Stream::Stdout)
+	}
+
+	///! Write each LogEntry to stdout in the --format given, starting with those
+	///! already loaded and continuing as lines are appended to the logfiles
+	pub async fn stream_entries(&mut self) -> Result<(), Error> {
+		let mut writer = EntryWriter::new(self.config.format, std::io::stdout())?;
+		for logfile in self.logfile_names.iter() {
+			if let Some(monitor) = self.monitors.get(logfile) {
+				for entry in monitor.metrics.log_history.iter() {
+					writer.write(entry)?;
+				}
+			}
+		}
+
+		let mut remote_rx = self.remote_rx.take().expect("remote_rx already taken");
+		loop {
+			let (source, line) = tokio::select! {
+				Some(line) = self.logfiles.next() => {
+					let line = line?;
+					(line.source().to_string_lossy().to_string(), line.line().to_string())
+				},
+				Some(remote_line) = remote_rx.recv() => (remote_line.source, remote_line.line),
+				else => return Ok(()),
+			};
+
+			if let Some(monitor) = self.monitors.get_mut(&source) {
+				let history_len = monitor.metrics.log_history.len();
+				monitor.append_to_content(&line)?;
+				for entry in monitor.metrics.log_history.get(history_len..).unwrap_or(&[]) {
+					writer.write(entry)?;
+				}
+			}
+		}
+	}
+
+	///! Feed synthetic logfile lines to a new LogMonitor at line_rate lines per second for duration
+	///!
+	///! Lines are generated by a separate task and queued, as lines from linemux are,
+	///! so the queue grows if processing can't keep up.
+	pub async fn stress_test_mode(&self, line_rate: u64, duration: Duration) -> StressTestResult {
+		let queued = std::sync::Arc::new(AtomicUsize::new(0));
+		let max_queued = std::sync::Arc::new(AtomicUsize::new(0));
+		let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+
+		let generator_queued = queued.clone();
+		let generator_max_queued = max_queued.clone();
+		let total_lines = (line_rate as f64 * duration.num_milliseconds() as f64 / 1000.0) as u64;
+		tokio::spawn(async move {
+			let start = std::time::Instant::now();
+			let mut interval = tokio::time::interval(STRESS_TEST_TICK);
+			let mut sent: u64 = 0;
+			while sent < total_lines {
+				interval.tick().await;
+				let due = (start.elapsed().as_secs_f64() * line_rate as f64) as u64;
+				while sent < due.min(total_lines) {
+					let depth = generator_queued.fetch_add(1, Ordering::SeqCst) + 1;
+					generator_max_queued.fetch_max(depth, Ordering::SeqCst);
+					if tx.send(synthetic_log_line(sent as usize)).is_err() {
+						return;
+					}
+					sent += 1;
+				}
+			}
+		});
+
+		let mut monitor = LogMonitor::new(&self.config, String::from("stress-test"), self.config.lines_max);
+		let mut result = StressTestResult {
+			lines_processed: 0,
+			lines_parsed: 0,
+			processing_time: std::time::Duration::default(),
+			max_queue_depth: 0,
+		};
+		while let Some(line) = rx.recv().await {
+			queued.fetch_sub(1, Ordering::SeqCst);
+			let history_len = monitor.metrics.log_history.len();
+			let start = std::time::Instant::now();
+			let processed = monitor.append_to_content(&line);
+			result.processing_time += start.elapsed();
+			result.lines_processed += 1;
+			if processed.is_ok() && monitor.metrics.log_history.len() > history_len {
+				result.lines_parsed += 1;
+			}
+		}
+		result.max_queue_depth = max_queued.load(Ordering::SeqCst);
+		result
+	}
+
+	///! Write the metrics of every monitored logfile to path as a JSON array of MetricsSnapshot
+	pub fn export_all_metrics_json(&self, path: &Path) -> std::io::Result<()> {
+		let file = File::create(path)?;
+		serde_json::to_writer_pretty(file, &self.metrics_snapshots())
+			.map_err(|e| Error::new(ErrorKind::Other, format!("json: {}", e)))
+	}
+
+	///! Export to --signal-export-path in the background (see PendingWrites)
+	pub fn export_on_signal(&mut self) {
+		let path = self.config.signal_export_path.clone();
+		let message = match serde_json::to_vec_pretty(&self.metrics_snapshots()) {
+			Ok(json) => {
+				let message = format!("exporting metrics to {}", path.display());
+				self.pending_writes.spawn(format!("export to {}", path.display()), move || {
+					std::fs::write(&path, json)
+				});
+				message
+			}
+			Err(e) => format!("error: export to {} failed: {}", path.display(), e),
+		};
+		self.dash_state.command_message = Some(message);
+	}
+
+	///! Write the topology of the monitored nodes to --graphviz-path (see topology_dot())
+	pub fn export_topology(&mut self) {
+		let monitors: Vec<&LogMonitor> = self
+			.logfile_names
+			.iter()
+			.filter_map(|logfile| self.monitors.get(logfile))
+			.filter(|monitor| !monitor.is_aggregate && !monitor.is_debug_dashboard_log)
+			.collect();
+		let dot = topology_dot(&monitors);
+		let path = self.config.graphviz_path.clone();
+		self.dash_state.command_message = Some(format!("saving topology to {}", path.display()));
+		self.pending_writes.spawn(format!("topology to {}", path.display()), move || {
+			std::fs::write(&path, dot)
+		});
+	}
+
+	///! Check for logfiles which have gone silent every timeout / 4
+	///!
+	///! The task sends timeout to App::watchdog_rx, which the event loop passes to
+	///! App::check_watchdog(). Drop the returned handle to cancel the watchdog.
+	pub fn watchdog(&self, timeout: Duration) -> WatchdogHandle {
+		let period = (timeout / 4).to_std().unwrap_or_default().max(WATCHDOG_MIN_PERIOD);
+		let mut watchdog_tx = self.watchdog_tx.clone();
+		let (task, abort_handle) = futures::future::abortable(async move {
+			loop {
+				tokio::time::delay_for(period).await;
+				if watchdog_tx.send(timeout).await.is_err() {
+					return;
+				}
+			}
+		});
+		tokio::spawn(task);
+		WatchdogHandle { abort_handle }
+	}
+
+	///! Raise a critical notification for each node with no logfile entry within timeout
+	pub fn check_watchdog(&mut self, timeout: Duration) {
+		let now = Utc::now();
+		self.foreach_monitor(|monitor| {
+			if !monitor.is_debug_dashboard_log {
+				monitor.metrics.check_silence(now, timeout);
+			}
+		});
+	}
+
+	///! Copy metrics to the Prometheus registry (see --emit-metrics-port)
+	///! and the web UI (see --web-ui-port)
+	pub fn export_metrics(&mut self) {
+		#[cfg(feature = "web-ui")]
+		{
+			if let Some(snapshots) = &self.web_ui_snapshots {
+				let latest = self.metrics_snapshots();
+				*snapshots.lock().unwrap() = latest;
+			}
+		}
+
+		if let Some(exporter) = &self.metrics_exporter {
+			for (_monitor_file, monitor) in self.monitors.iter() {
+				if !monitor.is_aggregate && !monitor.is_debug_dashboard_log {
+					exporter.update(monitor);
+				}
+			}
 		}
 	}
 
 	pub fn update_chunk_store_stats(&mut self) {
-		for (_monitor_file, monitor) in self.monitors.iter_mut() {
-			monitor.update_chunk_store_fsstats();
-			update_chunk_store_stats(&monitor.chunk_store_pathbuf, &mut monitor.chunk_store);
+		self.foreach_monitor(|monitor| {
+			if !monitor.is_aggregate {
+				monitor.update_chunk_store_fsstats();
+				update_chunk_store_stats(&monitor.chunk_store_pathbuf, &mut monitor.chunk_store);
+			}
+		});
+	}
+
+	///! Give the monitor of logfile to the display and filter settings of the monitor of from
+	pub fn clone_monitor_config(&mut self, from: &str, logfile: &str) -> Result<(), Error> {
+		let not_found = |name: &str| Error::new(ErrorKind::NotFound, format!("no monitor for: {}", name));
+		let mut target = self.monitors.remove(logfile).ok_or_else(|| not_found(logfile))?;
+		let result = match self.monitors.get(from) {
+			Some(source) => {
+				source.clone_config_into(&mut target);
+				Ok(())
+			}
+			None => Err(not_found(from)),
+		};
+		self.monitors.insert(logfile.to_string(), target);
+		result
+	}
+
+	///! Create (or replace) a read-only monitor which combines the metrics of the named monitors
+	pub fn merge_monitors(&mut self, names: &[&str], new_name: &str) -> Result<(), Error> {
+		if let Some(existing) = self.monitors.get(new_name) {
+			if !existing.is_aggregate {
+				return Err(Error::new(
+					ErrorKind::AlreadyExists,
+					format!("not an aggregate monitor: {}", new_name),
+				));
+			}
+		}
+
+		let mut aggregate = LogMonitor::new(&self.config, new_name.to_string(), self.config.lines_max);
+		aggregate.is_aggregate = true;
+		for name in names {
+			match self.monitors.get(*name) {
+				Some(monitor) => {
+					aggregate.metrics.merge_from(&monitor.metrics);
+					aggregate._append_to_content(&format!("Includes: {}", name))?;
+				}
+				None => {
+					return Err(Error::new(
+						ErrorKind::NotFound,
+						format!("no monitor for: {}", name),
+					))
+				}
+			}
+		}
+
+		if !self.logfile_names.iter().any(|name| name == new_name) {
+			self.logfile_names.push(new_name.to_string());
+		}
+		self.monitors.insert(new_name.to_string(), aggregate);
+		Ok(())
+	}
+
+	///! Merge all logfile monitors into a single aggregate and give it focus
+	pub fn merge_all_monitors(&mut self) {
+		let names: Vec<String> = self
+			.logfile_names
+			.iter()
+			.filter(|name| match self.monitors.get(*name) {
+				Some(monitor) => !monitor.is_aggregate && !monitor.is_debug_dashboard_log,
+				None => false,
+			})
+			.cloned()
+			.collect();
+		let names: Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+
+		match self.merge_monitors(&names, AGGREGATE_MONITOR_NAME) {
+			Ok(()) => self.set_logfile_with_focus(AGGREGATE_MONITOR_NAME.to_string()),
+			Err(e) => self.dash_state._debug_window(format!("merge failed: {}", e).as_str()),
+		}
+	}
+
+	///! Monitor a node which streams its logfile to addr over TCP
+	///! Lines are received by the event loop from App::remote_rx
+	pub fn connect_to_remote(&mut self, addr: SocketAddr) -> Result<(), Error> {
+		let name = remote_monitor_name(&addr);
+		if self.monitors.contains_key(&name) {
+			return Err(Error::new(
+				ErrorKind::AlreadyExists,
+				format!("already connected to: {}", addr),
+			));
+		}
+		if self.monitor_count() >= self.config.max_monitors {
+			return Err(max_monitors_reached(self.config.max_monitors));
+		}
+
+		let monitor = LogMonitor::new(&self.config, name.clone(), self.config.lines_max);
+		self.logfile_names.push(name.clone());
+		self.monitors.insert(name, monitor);
+		tokio::spawn(stream_lines(addr, self.remote_tx.clone()));
+		Ok(())
+	}
+
+	///! Number of monitored logfiles, which is limited by --max-monitors
+	pub fn monitor_count(&self) -> usize {
+		self.monitors
+			.values()
+			.filter(|monitor| !monitor.is_aggregate && !monitor.is_debug_dashboard_log)
+			.count()
+	}
+
+	///! Process a line as if it had been appended to logfile, without touching
+	///! the file system (for testing metrics parsing)
+	pub fn simulate_line(&mut self, logfile: &str, line: &str) -> std::io::Result<()> {
+		match self.monitors.get_mut(logfile) {
+			Some(monitor) => monitor.append_to_content(line),
+			None => Err(Error::new(
+				ErrorKind::NotFound,
+				format!("no monitor for: {}", logfile),
+			)),
+		}
+	}
+
+	///! Pass a line from a logfile or --remote to its monitor, and to the --tee file if shown
+	pub fn append_line(&mut self, logfile: &str, line: &str) -> std::io::Result<()> {
+		let shown = match self.get_monitor_for_file_path(&logfile.to_string()) {
+			Some(monitor) if monitor.is_debug_dashboard_log => {
+				monitor.append_to_content(line)?;
+				self.dash_state._debug_window(line);
+				return Ok(());
+			}
+			Some(monitor) => monitor.append_and_show(line)?,
+			None => {
+				self.dash_state._debug_window(format!("NO MONITOR FOR: {}", logfile).as_str());
+				return Ok(());
+			}
+		};
+		if let Some(shown) = shown {
+			self.tee_line(&shown);
+		}
+		Ok(())
+	}
+
+	///! Also write each logfile line shown in the dashboard to path, as shown,
+	///! after filtering by --min-severity and applying --transform (see --tee)
+	pub fn tee_to_file(&mut self, path: &Path) -> std::io::Result<()> {
+		self.tee = Some(BufWriter::new(File::create(path)?));
+		Ok(())
+	}
+
+	///! Write line to the --tee file, if any, stopping teeing if the write fails
+	fn tee_line(&mut self, line: &str) {
+		if let Some(tee) = self.tee.as_mut() {
+			if let Err(e) = writeln!(tee, "{}", line) {
+				warn!("--tee stopped: {}", e);
+				self.tee = None;
+			}
+		}
+	}
+
+	///! Flush lines written by tee_line(), on each tick
+	pub fn flush_tee(&mut self) {
+		if let Some(tee) = self.tee.as_mut() {
+			if let Err(e) = tee.flush() {
+				warn!("--tee stopped: {}", e);
+				self.tee = None;
+			}
 		}
 	}
 
@@ -257,7 +1026,7 @@ impl App {
 			}
 		}
 
-		if next_i == 0 && self.opt.debug_window && self.logfile_with_focus != DEBUG_WINDOW_NAME {
+		if next_i == 0 && self.config.debug_window && self.logfile_with_focus != DEBUG_WINDOW_NAME {
 			self.set_logfile_with_focus(DEBUG_WINDOW_NAME.to_string());
 			return;
 		}
@@ -288,7 +1057,7 @@ impl App {
 			}
 		}
 
-		if self.opt.debug_window
+		if self.config.debug_window
 			&& previous_i == len - 1
 			&& self.logfile_with_focus != DEBUG_WINDOW_NAME
 		{
@@ -307,17 +1076,30 @@ impl App {
 	}
 
 	pub fn handle_arrow_up(&mut self) {
-		if let Some(monitor) = self.get_monitor_with_focus() {
+		if self.dash_state.main_view == DashViewMain::DashSearch {
+			self.dash_state.search_selected = self.dash_state.search_selected.saturating_sub(1);
+		} else if let Some(monitor) = self.get_monitor_with_focus() {
 			do_bracketed_next_previous(&mut monitor.content, false);
-		} else if self.opt.debug_window {
+		} else if self.config.debug_window {
 			do_bracketed_next_previous(&mut self.dash_state.debug_window_list, false);
 		}
 	}
 
-	pub fn handle_arrow_down(&mut self) {
+	pub fn select_section(&mut self, next: bool) {
 		if let Some(monitor) = self.get_monitor_with_focus() {
+			monitor.select_section(next);
+		}
+	}
+
+	pub fn handle_arrow_down(&mut self) {
+		if self.dash_state.main_view == DashViewMain::DashSearch {
+			let results: usize = self.dash_state.search_results.iter().map(|(_, indices)| indices.len()).sum();
+			if self.dash_state.search_selected + 1 < results {
+				self.dash_state.search_selected += 1;
+			}
+		} else if let Some(monitor) = self.get_monitor_with_focus() {
 			do_bracketed_next_previous(&mut monitor.content, true);
-		} else if self.opt.debug_window {
+		} else if self.config.debug_window {
 			do_bracketed_next_previous(&mut self.dash_state.debug_window_list, true);
 		}
 	}
@@ -358,34 +1140,162 @@ fn do_bracketed_next_previous(list: &mut StatefulList<String>, next: bool) {
 	}
 }
 
-fn exit_with_usage(reason: &str) -> Result<App, std::io::Error> {
-	println!(
-		"Try '{} --help' for more information.",
-		Opt::clap().get_name()
-	);
-	return Err(Error::new(ErrorKind::Other, reason));
-}
+static WATCHDOG_MIN_PERIOD: std::time::Duration = std::time::Duration::from_secs(1);
+static PENDING_WRITES_POLL: std::time::Duration = std::time::Duration::from_millis(20);
 
-pub struct ChunkStoreSpec {
-	pub dir_name: String,
-	pub ui_name: String,
-	pub visible: bool,
+///! Counts file writes running on their own threads, so App::shutdown() can wait for them
+#[derive(Clone, Default)]
+pub struct PendingWrites {
+	count: std::sync::Arc<AtomicUsize>,
 }
 
-impl ChunkStoreSpec {
-	pub fn new(dir_name: &str, ui_name: &str, visible: bool) -> ChunkStoreSpec {
-		ChunkStoreSpec {
-			dir_name: String::from(dir_name),
-			ui_name: String::from(ui_name),
-			visible,
+impl PendingWrites {
+	///! Run write on a new thread, logging a warning naming description if it fails
+	pub fn spawn<F>(&self, description: String, write: F)
+	where
+		F: FnOnce() -> std::io::Result<()> + Send + 'static,
+	{
+		let count = self.count.clone();
+		count.fetch_add(1, Ordering::SeqCst);
+		std::thread::spawn(move || {
+			if let Err(e) = write() {
+				warn!("{} failed: {}", description, e);
+			}
+			count.fetch_sub(1, Ordering::SeqCst);
+		});
+	}
+
+	pub fn count(&self) -> usize {
+		self.count.load(Ordering::SeqCst)
+	}
+
+	///! Block until there are no pending writes or timeout has passed, returning true if none remain
+	pub fn wait(&self, timeout: std::time::Duration) -> bool {
+		let start = std::time::Instant::now();
+		while self.count() > 0 {
+			if start.elapsed() >= timeout {
+				return false;
+			}
+			std::thread::sleep(PENDING_WRITES_POLL);
 		}
+		true
 	}
 }
+static STRESS_TEST_TICK: std::time::Duration = std::time::Duration::from_millis(10);
 
-// Directories should correspond with those in maidsafe/sn_node/src/chunk_store/mod.rs
-lazy_static::lazy_static! {
-	static ref CHUNK_STORES: Vec::<ChunkStoreSpec> = vec!(
-		ChunkStoreSpec::new("append_only", "Append Only", true),
+///! Cancels the task started by App::watchdog() when dropped
+pub struct WatchdogHandle {
+	abort_handle: futures::future::AbortHandle,
+}
+
+impl Drop for WatchdogHandle {
+	fn drop(&mut self) {
+		self.abort_handle.abort();
+	}
+}
+
+///! Measurements from App::stress_test_mode()
+pub struct StressTestResult {
+	pub lines_processed: u64,
+	pub lines_parsed: u64, // Decoded into a LogEntry
+	pub processing_time: std::time::Duration, // Spent in LogMonitor::append_to_content()
+	pub max_queue_depth: usize, // Lines waiting to be processed
+}
+
+impl StressTestResult {
+	pub fn parse_success_rate(&self) -> f64 {
+		if self.lines_processed == 0 {
+			return 0.0;
+		}
+		self.lines_parsed as f64 / self.lines_processed as f64
+	}
+
+	pub fn time_per_line(&self) -> std::time::Duration {
+		self.processing_time / self.lines_processed.max(1) as u32
+	}
+}
+
+impl std::fmt::Display for StressTestResult {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		writeln!(f, "lines processed:    {}", self.lines_processed)?;
+		writeln!(f, "parse success rate: {:.1}%", self.parse_success_rate() * 100.0)?;
+		writeln!(f, "time per line:      {}µs", self.time_per_line().as_micros())?;
+		write!(f, "max queue depth:    {}", self.max_queue_depth)
+	}
+}
+
+///! A logfile line for App::stress_test_mode(), choosing the message by index
+fn synthetic_log_line(index: usize) -> String {
+	let messages = [
+		"Gossip message sent to 5 peers",
+		"Cache hit: key=8a3f2c",
+		"Signature verified in 85µs",
+		"Task completed: handle_message in 12ms",
+		"Added 3 to routing table",
+	];
+	format!(
+		"[sn_node] INFO {} [src/node/mod.rs:97] {}",
+		Utc::now().format("%Y-%m-%dT%H:%M:%S%.9f+00:00"),
+		messages[index % messages.len()]
+	)
+}
+
+///! Send on tx each time SIGUSR1 is received (see App::export_on_signal())
+async fn forward_export_signals(tx: mpsc::Sender<()>) {
+	#[cfg(unix)]
+	{
+		use tokio::signal::unix::{signal, SignalKind};
+		let mut signal_tx = tx.clone();
+		match signal(SignalKind::user_defined1()) {
+			Ok(mut signals) => {
+				while signals.recv().await.is_some() {
+					if signal_tx.send(()).await.is_err() {
+						return;
+					}
+				}
+			}
+			Err(e) => debug_log!(format!("unable to handle SIGUSR1: {}", e).as_str()),
+		}
+	}
+
+	// Keep tx open, so the event loop never sees the channel close
+	let _tx = tx;
+	futures::future::pending::<()>().await;
+}
+
+fn max_monitors_reached(max_monitors: usize) -> Error {
+	warn!("max monitors reached (--max-monitors {})", max_monitors);
+	Error::new(ErrorKind::Other, "max monitors reached")
+}
+
+fn exit_with_usage<T>(reason: &str) -> Result<T, std::io::Error> {
+	println!(
+		"Try '{} --help' for more information.",
+		Opt::clap().get_name()
+	);
+	return Err(Error::new(ErrorKind::Other, reason));
+}
+
+pub struct ChunkStoreSpec {
+	pub dir_name: String,
+	pub ui_name: String,
+	pub visible: bool,
+}
+
+impl ChunkStoreSpec {
+	pub fn new(dir_name: &str, ui_name: &str, visible: bool) -> ChunkStoreSpec {
+		ChunkStoreSpec {
+			dir_name: String::from(dir_name),
+			ui_name: String::from(ui_name),
+			visible,
+		}
+	}
+}
+
+// Directories should correspond with those in maidsafe/sn_node/src/chunk_store/mod.rs
+lazy_static::lazy_static! {
+	static ref CHUNK_STORES: Vec::<ChunkStoreSpec> = vec!(
+		ChunkStoreSpec::new("append_only", "Append Only", true),
 		ChunkStoreSpec::new("immutable", "Immutable", true),
 		ChunkStoreSpec::new("mutable", "Mutable", true),
 		ChunkStoreSpec::new("sequence", "Sequence", true),
@@ -457,7 +1367,13 @@ use fs2::{statvfs, FsStats};
 pub struct LogMonitor {
 	pub index: usize,
 	pub content: StatefulList<String>,
+	pub content_offset: usize, // First line of content visible in the UI
 	max_content: usize, // Limit number of lines in content
+	content_repetitions: Vec<usize>, // Count for each line of content (see deduplicate())
+	deduplicate_window: usize, // Zero unless deduplicating
+	pub archive_count: u32, // Times content has been archived (see archive())
+	log_rate: f64, // Lines per second, as last displayed (see displayed_log_rate())
+	log_rate_ticks: usize,
 	pub has_focus: bool,
 	pub logfile: String,
 	pub chunk_store_fsstats: Option<FsStats>,
@@ -466,13 +1382,78 @@ pub struct LogMonitor {
 	pub metrics: NodeMetrics,
 	pub metrics_status: StatefulList<String>,
 	pub is_debug_dashboard_log: bool,
+	pub is_aggregate: bool, // Metrics merged from other monitors (see App::merge_monitors())
+	min_severity: u8, // Ignore entries below this LogEntry::severity_rank()
+	own_id_prefix: Option<String>, // Lowercase, see apply_highlight_rules()
+	pub ansi_log: bool, // Render ANSI colours in content rather than removing them
+	transforms: Vec<LineTransform>, // Applied to each line of content (see --transform)
+}
+
+///! Write text to the system clipboard using the platform's clipboard command
+fn copy_to_clipboard(text: &str) -> std::io::Result<()> {
+	use std::process::{Command, Stdio};
+
+	let (program, args): (&str, &[&str]) = if cfg!(target_os = "macos") {
+		("pbcopy", &[])
+	} else if cfg!(target_os = "windows") {
+		("clip", &[])
+	} else {
+		("xclip", &["-selection", "clipboard"])
+	};
+	let mut child = Command::new(program).args(args).stdin(Stdio::piped()).spawn()?;
+	if let Some(stdin) = child.stdin.as_mut() {
+		stdin.write_all(text.as_bytes())?;
+	}
+	let status = child.wait()?;
+	if !status.success() {
+		return Err(Error::new(ErrorKind::Other, format!("{} exited with {}", program, status)));
+	}
+	Ok(())
+}
+
+static OWN_ID_PREFIX_LENGTH: usize = 8; // Node IDs are often abbreviated in logfiles
+
+static LOG_RATE_TICKS: usize = 5;
+
+///! A regex substitution applied to lines of logfile content (see --transform)
+#[derive(Clone, Debug)]
+pub struct LineTransform {
+	pub find: Regex,
+	pub replace: String,
+}
+
+impl std::str::FromStr for LineTransform {
+	type Err = String;
+
+	///! Parse 'regex:replacement', where the regex may not contain ':'
+	fn from_str(s: &str) -> Result<LineTransform, String> {
+		let mut parts = s.splitn(2, ':');
+		match (parts.next(), parts.next()) {
+			(Some(find), Some(replace)) => Ok(LineTransform {
+				find: Regex::new(find).map_err(|e| format!("invalid transform regex '{}': {}", find, e))?,
+				replace: replace.to_string(),
+			}),
+			_ => Err(format!("invalid transform '{}', expected 'regex:replacement'", s)),
+		}
+	}
+}
+
+///! Replace matches of each transform in line, in order
+pub fn transform_line(line: &str, transforms: &[LineTransform]) -> String {
+	let mut line = line.to_string();
+	for transform in transforms.iter() {
+		if let std::borrow::Cow::Owned(replaced) = transform.find.replace_all(&line, transform.replace.as_str()) {
+			line = replaced;
+		}
+	}
+	line
 }
 
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 static NEXT_MONITOR: AtomicUsize = AtomicUsize::new(0);
 
 impl LogMonitor {
-	pub fn new(opt: &Opt, f: String, max_lines: usize) -> LogMonitor {
+	pub fn new(config: &Config, f: String, max_lines: usize) -> LogMonitor {
 		let index = NEXT_MONITOR.fetch_add(1, Ordering::Relaxed);
 
 		let mut is_debug_dashboard_log = false;
@@ -491,14 +1472,28 @@ impl LogMonitor {
 			index,
 			logfile: f,
 			max_content: max_lines,
+			content_offset: 0,
+			content_repetitions: Vec::<usize>::new(),
+			own_id_prefix: config
+				.highlight_own_id
+				.as_ref()
+				.map(|id| id.to_lowercase().chars().take(OWN_ID_PREFIX_LENGTH).collect()),
+			ansi_log: config.ansi_log,
+			transforms: config.transforms.clone(),
+			deduplicate_window: if config.deduplicate { config.deduplicate_window } else { 0 },
+			archive_count: 0,
+			log_rate: 0.0,
+			log_rate_ticks: 0,
 			chunk_store_fsstats: None,
 			chunk_store_pathbuf,
 			chunk_store: ChunkStoreStatsAll::new(),
-			metrics: NodeMetrics::new(&opt),
+			metrics: NodeMetrics::new(&config),
 			content: StatefulList::with_items(vec![]),
 			has_focus: false,
 			metrics_status: StatefulList::with_items(vec![]),
 			is_debug_dashboard_log,
+			is_aggregate: false,
+			min_severity: config.min_severity.unwrap_or(0),
 		}
 	}
 
@@ -539,41 +1534,417 @@ impl LogMonitor {
 	}
 
 	pub fn append_to_content(&mut self, text: &str) -> Result<(), std::io::Error> {
-		if self.line_filter(&text) {
-			self._append_to_content(text)?; // Show in TUI
-			if self.is_debug_dashboard_log {
-				return Ok(());
-			}
+		self.append_and_show(text).map(|_| ())
+	}
+
+	///! As append_to_content(), returning the line as shown in content, or None if filtered out
+	pub fn append_and_show(&mut self, text: &str) -> Result<Option<String>, std::io::Error> {
+		if !self.line_filter(&text) {
+			return Ok(None);
+		}
+		let shown = transform_line(text, &self.transforms);
+		self._append_to_content(&shown)?; // Show in TUI
+		if !self.is_debug_dashboard_log {
 			self.metrics.gather_metrics(&text)?;
 		}
-		Ok(())
+		Ok(Some(shown))
 	}
 
 	pub fn _append_to_content(&mut self, text: &str) -> Result<(), std::io::Error> {
+		let len = self.content.items.len();
+		let window_start = len.saturating_sub(self.deduplicate_window);
+		if let Some(position) = self.content.items[window_start..].iter().rposition(|line| line == text) {
+			self.content_repetitions[window_start + position] += 1;
+			return Ok(());
+		}
+
 		self.content.items.push(text.to_string());
+		self.content_repetitions.push(1);
 		let len = self.content.items.len();
-		if len > self.max_content {
+		if len > self.max_content && is_memory_low() {
+			self.compress_content(LOW_MEMORY_CONTENT_RATIO);
+		} else if len > self.max_content {
 			self.content.items = self.content.items.split_off(len - self.max_content);
+			self.content_repetitions = self.content_repetitions.split_off(len - self.max_content);
 		} else {
 			self.content.state.select(Some(len - 1));
 		}
 		Ok(())
 	}
 
+	///! Replace matches of each transform in every line of content, in order
+	pub fn apply_transforms(&mut self, transforms: Vec<LineTransform>) -> std::io::Result<()> {
+		for line in self.content.items.iter_mut() {
+			*line = transform_line(line, &transforms);
+		}
+		Ok(())
+	}
+
+	///! Insert a '--- gap: Xm Ys ---' line between adjacent entries more than threshold_secs apart
+	///!
+	///! Lines which don't decode to a LogEntry with a time are skipped over rather than
+	///! treated as the end of a run. Returns the number of gaps inserted.
+	pub fn annotate_time_gaps(&mut self, threshold_secs: u64) -> usize {
+		let mut items = Vec::<String>::with_capacity(self.content.items.len());
+		let mut repetitions = Vec::<usize>::with_capacity(self.content_repetitions.len());
+		let mut previous_time: Option<DateTime<Utc>> = None;
+		let mut gaps = 0;
+		for (line, count) in self.content.items.drain(..).zip(self.content_repetitions.drain(..)) {
+			if let Some(time) = LogEntry::decode(&line).and_then(|entry| entry.time) {
+				if let Some(previous) = previous_time {
+					let gap = time.signed_duration_since(previous).num_seconds();
+					if gap > threshold_secs as i64 {
+						items.push(format!("--- gap: {}m {}s ---", gap / 60, gap % 60));
+						repetitions.push(1);
+						gaps += 1;
+					}
+				}
+				previous_time = Some(time);
+			}
+			items.push(line);
+			repetitions.push(count);
+		}
+		self.content.items = items;
+		self.content_repetitions = repetitions;
+		if gaps > 0 {
+			self.content.state.select(Some(self.content.items.len() - 1));
+		}
+		gaps
+	}
+
+	///! Copy display and filter settings to other, leaving its content, metrics and logfile
+	///!
+	///! Highlight rules are the same for every monitor, and there is no tail mode, so
+	///! these are the line limit, severity filter and deduplication window.
+	pub fn clone_config_into(&self, other: &mut LogMonitor) {
+		other.set_max_content(self.max_content);
+		other.has_focus = false;
+		other.min_severity = self.min_severity;
+		other.deduplicate_window = self.deduplicate_window;
+		other.own_id_prefix = self.own_id_prefix.clone();
+		other.ansi_log = self.ansi_log;
+		other.transforms = self.transforms.clone();
+	}
+
+	pub fn set_max_content(&mut self, max_lines: usize) {
+		self.max_content = max_lines;
+		let len = self.content.items.len();
+		if len > self.max_content {
+			self.content.items = self.content.items.split_off(len - self.max_content);
+			self.content_repetitions = self.content_repetitions.split_off(len - self.max_content);
+			self.content.state.select(Some(self.max_content - 1));
+		}
+	}
+
+	///! Write the content to a gzipped file at path and then clear it
+	pub fn archive(&mut self, path: &Path) -> std::io::Result<()> {
+		use flate2::{write::GzEncoder, Compression};
+
+		let mut encoder = GzEncoder::new(File::create(path)?, Compression::default());
+		for line in self.content.items.iter() {
+			writeln!(encoder, "{}", line)?;
+		}
+		encoder.finish()?;
+
+		self.content.items.clear();
+		self.content_repetitions.clear();
+		self.content.state.select(None);
+		self.content_offset = 0;
+		self.archive_count += 1;
+		Ok(())
+	}
+
+	///! Where archive() will write the content: beside the logfile, numbered to
+	///! avoid overwriting earlier archives
+	pub fn next_archive_path(&self) -> PathBuf {
+		PathBuf::from(format!("{}.vdash-{}.log.gz", self.logfile, self.archive_count + 1))
+	}
+
+	///! Keep ratio of the content, removing the least severe lines first and
+	///! then the oldest. Returns the number of lines removed.
+	pub fn compress_content(&mut self, ratio: f64) -> usize {
+		let len = self.content.items.len();
+		let to_remove = ((1.0 - ratio.max(0.0).min(1.0)) * len as f64).round() as usize;
+		if to_remove == 0 {
+			return 0;
+		}
+
+		let mut remove = vec![false; len];
+		let mut removed = 0;
+		for (i, line) in self.content.items.iter().enumerate() {
+			if removed == to_remove {
+				break;
+			}
+			// As LogMonitor::apply_highlight_rules(), avoiding a full decode
+			let category = line.split_whitespace().nth(1).unwrap_or("");
+			if LogEntry::category_severity_rank(category) == 1 {
+				remove[i] = true;
+				removed += 1;
+			}
+		}
+		for flag in remove.iter_mut() {
+			if removed == to_remove {
+				break;
+			}
+			if !*flag {
+				*flag = true;
+				removed += 1;
+			}
+		}
+
+		let mut flags = remove.iter();
+		self.content.items.retain(|_| !*flags.next().unwrap());
+		let mut flags = remove.iter();
+		self.content_repetitions.retain(|_| !*flags.next().unwrap());
+
+		let len = self.content.items.len();
+		self.content.state.select(if len > 0 { Some(len - 1) } else { None });
+		removed
+	}
+
+	///! Remove entries of log_history and lines of content logged before time
+	///!
+	///! Returns the number of entries and lines removed. Lines without a time are kept.
+	pub fn truncate_before(&mut self, time: DateTime<Utc>) -> usize {
+		let is_old = |entry_time: Option<DateTime<Utc>>| entry_time.map_or(false, |t| t < time);
+
+		let history_len = self.metrics.log_history.len();
+		self.metrics.log_history.retain(|entry| !is_old(entry.time));
+		let history_removed = history_len - self.metrics.log_history.len();
+
+		let remove: Vec<bool> = self
+			.content
+			.items
+			.iter()
+			.map(|line| is_old(LogEntry::decode(line).and_then(|entry| entry.time)))
+			.collect();
+		let content_removed = remove.iter().filter(|flag| **flag).count();
+		if content_removed > 0 {
+			let mut flags = remove.iter();
+			self.content.items.retain(|_| !*flags.next().unwrap());
+			let mut flags = remove.iter();
+			self.content_repetitions.retain(|_| !*flags.next().unwrap());
+			self.content_offset = self.content_offset.saturating_sub(content_removed);
+
+			let len = self.content.items.len();
+			self.content.state.select(if len > 0 { Some(len - 1) } else { None });
+		}
+		history_removed + content_removed
+	}
+
+	///! Show a line which repeats one of the last window lines by counting
+	///! it against the earlier line. A window of zero turns this off.
+	pub fn deduplicate(&mut self, window: usize) {
+		self.deduplicate_window = window;
+	}
+
+	///! Current problems with this node (see App::health_check())
+	pub fn health_issues(&self) -> Vec<HealthIssue> {
+		let mut issues = Vec::<HealthIssue>::new();
+		let mut push_issue = |kind: HealthIssueKind, message: String| {
+			issues.push(HealthIssue {
+				monitor: self.logfile.clone(),
+				kind,
+				message,
+			})
+		};
+		let metrics = &self.metrics;
+
+		if let Some(errors) = metrics.errors_timeline.get_bucket_set("1 minute columns") {
+			let errors_this_minute = errors.buckets().last().cloned().unwrap_or(0);
+			if errors_this_minute >= HEALTH_ERRORS_PER_MINUTE {
+				push_issue(
+					HealthIssueKind::HighErrorRate,
+					format!("{} errors in the last minute", errors_this_minute),
+				);
+			}
+		}
+
+		if let Some(most_recent) = metrics.most_recent {
+			let minutes = (Utc::now() - most_recent).num_minutes();
+			if minutes >= HEALTH_STALE_LOG_MINUTES {
+				push_issue(
+					HealthIssueKind::StaleLog,
+					format!("nothing logged for {} minutes", minutes),
+				);
+			}
+		}
+
+		if metrics.quorum_known && !metrics.quorum {
+			push_issue(HealthIssueKind::QuorumLost, String::from("quorum lost"));
+		}
+
+		for (name, timeline) in [("GETS", &metrics.gets_timeline), ("PUTS", &metrics.puts_timeline)].iter() {
+			if let Some(bucket_set) = timeline.get_bucket_set("1 minute columns") {
+				let buckets = bucket_set.buckets();
+				if let Some((latest, earlier)) = buckets.split_last() {
+					let recent = &earlier[earlier.len().saturating_sub(10)..];
+					let average = recent.iter().sum::<u64>() / (recent.len().max(1) as u64);
+					if *latest >= HEALTH_ANOMALY_MINIMUM && *latest > average * HEALTH_ANOMALY_FACTOR {
+						push_issue(
+							HealthIssueKind::AnomalyDetected,
+							format!("{} {} in the last minute (average {})", latest, name, average),
+						);
+					}
+				}
+			}
+		}
+
+		if let Some(fsstats) = &self.chunk_store_fsstats {
+			let total = fsstats.total_space();
+			if total > 0 && fsstats.free_space() * 100 / total < HEALTH_DISK_FREE_PERCENT {
+				push_issue(
+					HealthIssueKind::DiskNearFull,
+					format!("only {}% of disk space free", fsstats.free_space() * 100 / total),
+				);
+			}
+		}
+		if let (Some(bytes), Some(alert_bytes)) = (metrics.disk_usage_bytes, metrics.disk_alert_bytes) {
+			if bytes > alert_bytes {
+				push_issue(
+					HealthIssueKind::DiskNearFull,
+					format!("disk usage {} bytes exceeds alert level", bytes),
+				);
+			}
+		}
+
+		issues
+	}
+
+	///! Lines per second logged during the minute up to the most recent entry
+	pub fn compute_log_rate(&self) -> f64 {
+		let most_recent = match self.metrics.most_recent {
+			Some(most_recent) => most_recent,
+			None => return 0.0,
+		};
+		let since = most_recent - Duration::seconds(60);
+		let lines = self
+			.metrics
+			.log_history
+			.iter()
+			.rev()
+			.take_while(|entry| entry.time.map_or(true, |time| time > since))
+			.filter(|entry| entry.time.is_some())
+			.count();
+		lines as f64 / 60.0
+	}
+
+	///! The message of the most recent ERROR entry in log_history
+	pub fn latest_error(&self) -> Option<&str> {
+		self.metrics
+			.log_history
+			.iter()
+			.rev()
+			.find(|entry| entry.category.eq("ERROR"))
+			.map(|entry| entry.message.as_str())
+	}
+
+	///! The log rate for display, recalculated every LOG_RATE_TICKS renders to avoid jitter
+	pub fn displayed_log_rate(&mut self) -> f64 {
+		if self.log_rate_ticks % LOG_RATE_TICKS == 0 {
+			self.log_rate = self.compute_log_rate();
+		}
+		self.log_rate_ticks += 1;
+		self.log_rate
+	}
+
+	///! Indices of the lines of content matching pattern
+	pub fn search(&self, pattern: &Regex) -> Vec<usize> {
+		self.content
+			.items
+			.iter()
+			.enumerate()
+			.filter(|(_, line)| pattern.is_match(line))
+			.map(|(index, _)| index)
+			.collect()
+	}
+
+	///! Number of lines of content matching the regex pattern, leaving the selection unchanged
+	pub fn grep_count(&self, pattern: &str) -> Result<usize, regex::Error> {
+		let regex = Regex::new(pattern)?;
+		Ok(self.content.items.iter().filter(|line| regex.is_match(line)).count())
+	}
+
+	///! How many times the line of content at index has been seen
+	pub fn repetition_count(&self, index: usize) -> usize {
+		self.content_repetitions.get(index).cloned().unwrap_or(1)
+	}
+
+	///! Move the content selection to the next (or previous) section banner
+	pub fn select_section(&mut self, next: bool) {
+		let len = self.content.items.len();
+		if len == 0 {
+			return;
+		}
+
+		let selected = self.content.state.selected().unwrap_or(len - 1);
+		let items = &self.content.items;
+		let metrics = &self.metrics;
+		let found = if next {
+			(selected + 1..len).find(|&i| metrics.is_section_header(&items[i]))
+		} else {
+			(0..selected).rev().find(|&i| metrics.is_section_header(&items[i]))
+		};
+		if let Some(index) = found {
+			self.content.state.select(Some(index));
+		}
+	}
+
+	///! Re-run the metrics parsers over the stored log_history, for example
+	///! after a parser has changed or a saved log_history has been loaded
+	pub fn reparse_history(&mut self) -> std::io::Result<()> {
+		self.metrics.reparse_history();
+		Ok(())
+	}
+
+	///! Up to count lines of content beginning at start, so that only visible lines are rendered
+	pub fn content_window(&self, start: usize, count: usize) -> &[String] {
+		let len = self.content.items.len();
+		let start = start.min(len);
+		let end = start.saturating_add(count).min(len);
+		&self.content.items[start..end]
+	}
+
 	// Some logfile lines are too numerous to include so we ignore them
 	// Returns true if the line is to be processed
-	fn line_filter(&mut self, _line: &str) -> bool {
+	fn line_filter(&mut self, line: &str) -> bool {
+		if self.min_severity > 0 {
+			if let Some(entry) = LogEntry::decode(line) {
+				return entry.severity_rank() >= self.min_severity;
+			}
+		}
 		true
 	}
+
+	///! Style for displaying a logfile line, based on its severity and
+	///! whether it mentions the node ID given by --highlight-own-id
+	pub fn apply_highlight_rules(&self, line: &str) -> Style {
+		let mut style = Style::default().fg(Color::Black).bg(Color::White);
+		if let Some(prefix) = &self.own_id_prefix {
+			if line.to_lowercase().contains(prefix.as_str()) {
+				style = style.add_modifier(Modifier::BOLD);
+			}
+		}
+
+		// Avoid a full LogEntry::decode() because this is called on every redraw
+		let category = line.split_whitespace().nth(1).unwrap_or("");
+		match LogEntry::category_severity_rank(category) {
+			4 => style.fg(Color::Red),
+			3 => style.fg(Color::Magenta),
+			1 => style.fg(Color::DarkGray),
+			_ => style,
+		}
+	}
 }
 
 use regex::Regex;
+pub static LOG_LINE_PATTERN_TIME_FORMAT: &str = "%+";
 lazy_static::lazy_static! {
-	static ref LOG_LINE_PATTERN: Regex =
+	pub static ref LOG_LINE_PATTERN: Regex =
 		Regex::new(r"(?P<module>^\[[A-Z,a-z,_,-]*\]) (?P<category>[A-Z]{4,6}) (?P<time_string>[^ ]{35}) (?P<source>\[.*\]) (?P<message>.*)").expect("The regex failed to compile. This is a bug.");
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum NodeAgebracket {
 	Unknown,
 	Infant,
@@ -605,12 +1976,82 @@ pub struct TimelineSet {
 	bucket_sets: HashMap<&'static str, BucketSet>,
 }
 
+static GAP_MIN_BUCKETS: usize = 3; // See BucketSet::fill_gaps()
+
+///! The smallest sample which at least percent of samples are no greater than
+fn percentile(samples: &[u64], percent: usize) -> Option<u64> {
+	if samples.is_empty() {
+		return None;
+	}
+	let mut sorted = samples.to_vec();
+	sorted.sort_unstable();
+	let index = (sorted.len() * percent + 99) / 100;
+	Some(sorted[index.max(1).min(sorted.len()) - 1])
+}
+
+///! Count in the last complete minute of timeline (or the current minute if none is complete)
+fn last_minute_count(timeline: &TimelineSet) -> u64 {
+	match timeline.get_bucket_set("1 minute columns") {
+		Some(bucket_set) => {
+			let mut recent = bucket_set.buckets.iter().rev();
+			let current = recent.next();
+			recent.next().or(current).copied().unwrap_or(0)
+		}
+		None => 0,
+	}
+}
+
+#[derive(Debug)]
+pub enum MergeError {
+	NoBucketSets(String), // Name of the TimelineSet
+}
+
+impl std::fmt::Display for MergeError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			MergeError::NoBucketSets(name) => write!(f, "timeline '{}' has no bucket sets", name),
+		}
+	}
+}
+
+#[derive(Debug)]
+pub enum AlignError {
+	DurationMismatch(Duration, Duration), // bucket_duration of each BucketSet
+}
+
+impl std::fmt::Display for AlignError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			AlignError::DurationMismatch(a, b) => write!(
+				f,
+				"bucket durations differ ({}s and {}s)",
+				a.num_seconds(),
+				b.num_seconds()
+			),
+		}
+	}
+}
+
+// Backslash escape each of special in an InfluxDB line protocol name or tag value
+fn influxdb_escape(s: &str, special: &str) -> String {
+	let mut escaped = String::with_capacity(s.len());
+	for c in s.chars() {
+		if special.contains(c) {
+			escaped.push('\\');
+		}
+		escaped.push(c);
+	}
+	escaped
+}
+
 pub struct BucketSet {
 	pub bucket_time: Option<DateTime<Utc>>,
 	pub total_duration: Duration,
 	pub bucket_duration: Duration,
 	pub max_buckets: usize,
 	pub buckets: Vec<u64>,
+	buckets_filled: usize, // Buckets which have had time to collect values
+	is_rate: bool, // Values are per second (see normalize_to_rate())
 }
 
 impl TimelineSet {
@@ -630,10 +2071,106 @@ impl TimelineSet {
 			.insert(name, BucketSet::new(duration, max_buckets));
 	}
 
-	pub fn get_bucket_set(&mut self, bucket_set_name: &str) -> Option<&BucketSet> {
+	pub fn get_bucket_set(&self, bucket_set_name: &str) -> Option<&BucketSet> {
 		self.bucket_sets.get(bucket_set_name)
 	}
 
+	pub fn set_max_buckets(&mut self, max_buckets: usize) {
+		for (_name, bs) in self.bucket_sets.iter_mut() {
+			bs.set_max_buckets(max_buckets);
+		}
+	}
+
+	///! Move the time of every BucketSet, such as to correct for a timezone
+	pub fn shift_time(&mut self, delta: Duration) {
+		for (_name, bs) in self.bucket_sets.iter_mut() {
+			if let Some(bucket_time) = bs.bucket_time {
+				bs.bucket_time = Some(bucket_time + delta);
+			}
+		}
+	}
+
+	///! Lines of InfluxDB line protocol for every bucket, for use with 'influx write':
+	///!	measurement,scale=1\ minute\ columns,tag1=v1 puts=3i 1608302000000000000
+	///!
+	///! The field is the lowercase timeline name, the 'scale' tag names the BucketSet and
+	///! each bucket is timestamped (in nanoseconds) with its start time.
+	pub fn to_influxdb_line_protocol(&self, measurement: &str, tags: &HashMap<&str, &str>) -> String {
+		let mut sorted_tags: Vec<(&&str, &&str)> = tags.iter().collect();
+		sorted_tags.sort();
+		let tags: String = sorted_tags
+			.iter()
+			.map(|(key, value)| format!(",{}={}", influxdb_escape(key, ",= "), influxdb_escape(value, ",= ")))
+			.collect();
+		let measurement = influxdb_escape(measurement, ", ");
+		let field = influxdb_escape(&self.name.to_lowercase(), ",= ");
+
+		let mut names: Vec<&&'static str> = self.bucket_sets.keys().collect();
+		names.sort();
+		let mut lines = String::new();
+		for name in names {
+			let bs = &self.bucket_sets[*name];
+			if let Some(bucket_time) = bs.bucket_time {
+				let newest = bs.buckets.len() as i32 - 1;
+				for (i, value) in bs.buckets.iter().enumerate() {
+					let start = bucket_time - bs.bucket_duration * (newest - i as i32);
+					lines.push_str(&format!(
+						"{},scale={}{} {}={}i {}\n",
+						measurement,
+						influxdb_escape(name, ",= "),
+						tags,
+						field,
+						value,
+						start.timestamp() * 1_000_000_000 + start.timestamp_subsec_nanos() as i64
+					));
+				}
+			}
+		}
+		lines
+	}
+
+	///! One BucketSet holding the element-wise sum of every BucketSet, aligned on
+	///! the most recent bucket with shorter sets padded with zeroes
+	///!
+	///! The result has the bucket_duration and bucket_time of the finest BucketSet
+	pub fn sum_across_sets(&self) -> Result<BucketSet, MergeError> {
+		let finest = self
+			.bucket_sets
+			.values()
+			.min_by_key(|bs| bs.bucket_duration)
+			.ok_or_else(|| MergeError::NoBucketSets(self.name.clone()))?;
+		let len = self.bucket_sets.values().map(|bs| bs.buckets.len()).max().unwrap_or(0);
+		let max_buckets = self.bucket_sets.values().map(|bs| bs.max_buckets).max().unwrap_or(0);
+
+		let mut sum = BucketSet::new(finest.bucket_duration, max_buckets);
+		sum.bucket_time = finest.bucket_time;
+		sum.buckets = vec![0; len];
+		// Bucket durations differ so sum aligned on the most recent bucket
+		for bs in self.bucket_sets.values() {
+			let offset = len - bs.buckets.len();
+			for (i, value) in bs.buckets.iter().enumerate() {
+				sum.buckets[offset + i] += value;
+			}
+		}
+		Ok(sum)
+	}
+
+	///! Empty every BucketSet
+	pub fn reset(&mut self) {
+		for (_name, bs) in self.bucket_sets.iter_mut() {
+			bs.reset();
+		}
+	}
+
+	///! Add the buckets of each BucketSet in other to the corresponding BucketSet
+	pub fn merge_from(&mut self, other: &TimelineSet) {
+		for (name, bs) in self.bucket_sets.iter_mut() {
+			if let Some(other_bs) = other.bucket_sets.get(name) {
+				bs.merge_from(other_bs);
+			}
+		}
+	}
+
 	///! Update all bucket_sets with new current time
 	///!
 	///! Call significantly more frequently than the smallest BucketSet duration
@@ -653,61 +2190,121 @@ impl TimelineSet {
 						if bs.buckets.len() > bs.max_buckets {
 							bs.buckets.remove(0);
 						}
+						bs.buckets_filled = (bs.buckets_filled + 1).min(bs.max_buckets);
 					}
 				}
 			} else {
 				bs.bucket_time = new_time;
+				if new_time.is_some() {
+					bs.buckets_filled = 1;
+				}
 			}
 		}
 	}
 
 	fn increment_value(&mut self, time: Option<DateTime<Utc>>) {
 		// debug_log!("increment_value()");
+		self.add_value(time, 1);
+	}
+
+	///! Add to the count for time, such as an amount rather than a single event
+	fn add_value(&mut self, time: Option<DateTime<Utc>>, value: u64) {
 		if let Some(time) = time {
 			for (_name, bs) in self.bucket_sets.iter_mut() {
 				// debug_log!(format!("name       : {}", _name).as_str());
-				let mut index = Some(bs.buckets.len() - 1);
-				// debug_log!(format!("time       : {}", time).as_str());
-				if let Some(bucket_time) = bs.bucket_time {
-				// debug_log!(format!("bucket_time: {}", bucket_time).as_str());
-					if time.lt(&bucket_time) {
-						// Use the closest bucket to this time
-						// debug_log!("increment (closest bucket)");
-						let time_difference = (bucket_time - time).num_nanoseconds();
-						let bucket_duration = bs.bucket_duration.num_nanoseconds();
-						if time_difference.and(bucket_duration).is_some() {
-							let buckets_behind = time_difference.unwrap() / bucket_duration.unwrap();
-							if buckets_behind as usize > bs.buckets.len() {
-								// debug_log!(format!("increment DISCARDED buckets_behind: {}", buckets_behind).as_str());
-								index = None;
-							} else {
-								// debug_log!(format!("increment INCLUDED buckets_behind: {}", buckets_behind).as_str());
-								index = Some(bs.buckets.len() - 1 - buckets_behind as usize);
-							}
-						}
-					}
-				}
-				if let Some(index) = index {
+				if let Some(index) = bs.index_for_time(time) {
 					// debug_log!(format!("increment index: {}", index).as_str());
-					bs.buckets[index] += 1;
+					bs.buckets[index] += value;
 				}
 			}
 		} else {
 			debug_log!("increment FAIL");
 		}
 	}
-}
 
-impl BucketSet {
-	pub fn new(bucket_duration: Duration, max_buckets: usize) -> BucketSet {
-		BucketSet {
+	///! Record a value (rather than a count) such as a level or size
+	fn set_value(&mut self, time: Option<DateTime<Utc>>, value: u64) {
+		if let Some(time) = time {
+			for (_name, bs) in self.bucket_sets.iter_mut() {
+				if let Some(index) = bs.index_for_time(time) {
+					bs.buckets[index] = value;
+				}
+			}
+		} else {
+			debug_log!("set_value FAIL");
+		}
+	}
+}
+
+impl BucketSet {
+	pub fn new(bucket_duration: Duration, max_buckets: usize) -> BucketSet {
+		BucketSet {
 			bucket_duration,
 			max_buckets,
 			total_duration: bucket_duration * max_buckets as i32,
 
 			bucket_time: None,
 			buckets: vec![0; max_buckets],
+			buckets_filled: 0,
+			is_rate: false,
+		}
+	}
+
+	///! Index of the bucket closest to time, or None if time is before the first bucket
+	pub fn index_for_time(&self, time: DateTime<Utc>) -> Option<usize> {
+		let mut index = Some(self.buckets.len() - 1);
+		// debug_log!(format!("time       : {}", time).as_str());
+		if let Some(bucket_time) = self.bucket_time {
+			// debug_log!(format!("bucket_time: {}", bucket_time).as_str());
+			if time.lt(&bucket_time) {
+				// Use the closest bucket to this time
+				let time_difference = (bucket_time - time).num_nanoseconds();
+				let bucket_duration = self.bucket_duration.num_nanoseconds();
+				if time_difference.and(bucket_duration).is_some() {
+					let buckets_behind = time_difference.unwrap() / bucket_duration.unwrap();
+					if buckets_behind as usize >= self.buckets.len() {
+						// debug_log!(format!("DISCARDED buckets_behind: {}", buckets_behind).as_str());
+						index = None;
+					} else {
+						// debug_log!(format!("INCLUDED buckets_behind: {}", buckets_behind).as_str());
+						index = Some(self.buckets.len() - 1 - buckets_behind as usize);
+					}
+				}
+			}
+		}
+		index
+	}
+
+	///! Change the number of buckets, keeping the most recent
+	pub fn set_max_buckets(&mut self, max_buckets: usize) {
+		let len = self.buckets.len();
+		if max_buckets < len {
+			self.buckets = self.buckets.split_off(len - max_buckets);
+		} else {
+			let mut buckets = vec![0; max_buckets - len];
+			buckets.append(&mut self.buckets);
+			self.buckets = buckets;
+		}
+		self.max_buckets = max_buckets;
+		self.total_duration = self.bucket_duration * max_buckets as i32;
+		self.buckets_filled = self.buckets_filled.min(max_buckets);
+	}
+
+	///! Fraction of buckets yet to cover a full history, from 1.0 (none) to 0.0 (full)
+	///!
+	///! The buckets vector is always max_buckets long (padded with zeros),
+	///! so this is based on the number of buckets which time has filled.
+	pub fn capacity_remaining(&self) -> f64 {
+		if self.max_buckets == 0 {
+			return 0.0;
 		}
+		(self.max_buckets - self.buckets_filled) as f64 / self.max_buckets as f64
+	}
+
+	pub fn reset(&mut self) {
+		self.buckets_filled = 0;
+		self.bucket_time = None;
+		self.buckets = vec![0; self.max_buckets];
 	}
 
 	pub fn set_bucket_value(&mut self, value: u64) {
@@ -727,12 +2324,332 @@ impl BucketSet {
 	pub fn buckets_mut(&mut self) -> &mut Vec<u64> {
 		&mut self.buckets
 	}
+
+	///! Convert each bucket from a count to a count per second, rounded down
+	///!
+	///! This can't be undone, and does nothing if the buckets are already rates.
+	pub fn normalize_to_rate(&mut self) {
+		if self.is_rate {
+			return;
+		}
+		let seconds = self.bucket_duration.num_seconds().max(1) as u64;
+		for bucket in self.buckets.iter_mut() {
+			*bucket /= seconds;
+		}
+		self.is_rate = true;
+	}
+
+	///! True if the buckets hold counts per second rather than counts
+	pub fn is_rate(&self) -> bool {
+		self.is_rate
+	}
+
+	///! Copies of the buckets of self and other with the shorter padded with leading
+	///! zeros, so that both have equal length and are aligned on the most recent bucket
+	pub fn align_with(&self, other: &BucketSet) -> Result<(Vec<u64>, Vec<u64>), AlignError> {
+		if self.bucket_duration != other.bucket_duration {
+			return Err(AlignError::DurationMismatch(self.bucket_duration, other.bucket_duration));
+		}
+
+		let len = self.buckets.len().max(other.buckets.len());
+		let pad = |buckets: &Vec<u64>| {
+			let mut padded = vec![0; len - buckets.len()];
+			padded.extend_from_slice(buckets);
+			padded
+		};
+		Ok((pad(&self.buckets), pad(&other.buckets)))
+	}
+
+	///! Add the buckets of other to these, aligned on the most recent bucket
+	///!
+	///! Does nothing if the bucket durations differ
+	pub fn merge_from(&mut self, other: &BucketSet) {
+		let (mine, theirs) = match self.align_with(other) {
+			Ok(aligned) => aligned,
+			Err(e) => {
+				warn!("unable to merge bucket sets: {}", e);
+				return;
+			}
+		};
+		let skip = mine.len() - self.buckets.len();
+		for (i, bucket) in self.buckets.iter_mut().enumerate() {
+			*bucket = mine[skip + i] + theirs[skip + i];
+		}
+		self.buckets_filled = self.buckets_filled.max(other.buckets_filled).min(self.max_buckets);
+	}
+
+	///! Fraction of the maximum possible events which occurred, from 0.0 (idle) to 1.0 (saturated)
+	///! computed as sum / (total_duration_seconds * max_buckets)
+	pub fn event_density(&self) -> f64 {
+		let capacity = self.total_duration.num_seconds() as f64 * self.max_buckets as f64;
+		if capacity <= 0.0 {
+			return 0.0;
+		}
+		let sum: u64 = self.buckets.iter().sum();
+		(sum as f64 / capacity).min(1.0)
+	}
+
+	///! Label buckets for event markers, given (bucket index, label) pairs
+	///! Returns one entry per bucket, with None for buckets without a label
+	pub fn annotate(&self, annotations: &[(usize, String)]) -> Vec<Option<String>> {
+		let mut labels = vec![None; self.buckets.len()];
+		for (index, label) in annotations {
+			if let Some(entry) = labels.get_mut(*index) {
+				*entry = Some(label.clone());
+			}
+		}
+		labels
+	}
+
+	///! Write the buckets in a flat text format for loading into RRDtool:
+	///!	DS:value:COUNTER:<heartbeat>:0:U
+	///!	update <timestamp>:<value>
+	///! with one update per bucket, timestamped with the start of the bucket
+	pub fn serialize_to_rrd(&self, writer: &mut dyn Write) -> std::io::Result<()> {
+		let step = self.bucket_duration.num_seconds().max(1);
+		writeln!(writer, "DS:value:COUNTER:{}:0:U", step * 2)?;
+
+		if let Some(bucket_time) = self.bucket_time {
+			let newest = self.buckets.len() as i32 - 1;
+			for (i, value) in self.buckets.iter().enumerate() {
+				let start = bucket_time - self.bucket_duration * (newest - i as i32);
+				writeln!(writer, "update {}:{}", start.timestamp(), value)?;
+			}
+		}
+		Ok(())
+	}
+
+	///! The buckets in Prometheus text exposition format:
+	///!	# HELP <metric_name> ...
+	///!	# TYPE <metric_name> <metric_type>
+	///!	<metric_name>{<labels>} <value> <timestamp>
+	///! with one sample per bucket, timestamped (in milliseconds) with the start of the bucket
+	pub fn serialize_to_prometheus(&self, metric_name: &str, labels: &str, metric_type: &str) -> String {
+		let mut text = format!(
+			"# HELP {} vdash timeline in {}s buckets\n# TYPE {} {}\n",
+			metric_name,
+			self.bucket_duration.num_seconds(),
+			metric_name,
+			metric_type
+		);
+		let labels = if labels.is_empty() { String::new() } else { format!("{{{}}}", labels) };
+		if let Some(bucket_time) = self.bucket_time {
+			let newest = self.buckets.len() as i32 - 1;
+			for (i, value) in self.buckets.iter().enumerate() {
+				let start = bucket_time - self.bucket_duration * (newest - i as i32);
+				text.push_str(&format!("{}{} {} {}\n", metric_name, labels, value, start.timestamp_millis()));
+			}
+		}
+		text
+	}
+
+	///! Fill runs of more than GAP_MIN_BUCKETS zero buckets, such as while the node
+	///! was paused or the system was asleep
+	///!
+	///! Gaps at the start are left as zeroes, and Interpolate leaves a gap at the end
+	pub fn fill_gaps(&mut self, strategy: GapFillStrategy) {
+		if strategy == GapFillStrategy::Zero {
+			return;
+		}
+
+		let len = self.buckets.len();
+		let mut i = 0;
+		while i < len {
+			if self.buckets[i] != 0 {
+				i += 1;
+				continue;
+			}
+			let start = i;
+			while i < len && self.buckets[i] == 0 {
+				i += 1;
+			}
+			if i - start <= GAP_MIN_BUCKETS || start == 0 {
+				continue;
+			}
+
+			let before = self.buckets[start - 1];
+			match (strategy, self.buckets.get(i).copied()) {
+				(GapFillStrategy::Hold, _) => {
+					for bucket in &mut self.buckets[start..i] {
+						*bucket = before;
+					}
+				}
+				(GapFillStrategy::Interpolate, Some(after)) => {
+					let steps = (i - start + 1) as f64;
+					for (step, bucket) in self.buckets[start..i].iter_mut().enumerate() {
+						let fraction = (step + 1) as f64 / steps;
+						*bucket = (before as f64 + (after as f64 - before as f64) * fraction).round() as u64;
+					}
+				}
+				_ => {}
+			}
+		}
+	}
+
+	///! Rate of the most recent complete bucket (or the current bucket if none is
+	///! complete), per second of bucket_duration
+	pub fn rate_per_second(&self) -> f64 {
+		let mut recent = self.buckets.iter().rev();
+		let current = recent.next();
+		let value = recent.next().or(current).copied().unwrap_or(0);
+		let seconds = self.bucket_duration.num_milliseconds() as f64 / 1000.0;
+		if seconds > 0.0 {
+			value as f64 / seconds
+		} else {
+			0.0
+		}
+	}
+
+	///! Index of the first bucket of the most recent run of at least min_run buckets
+	///! whose values are all within tolerance percent of the largest, such as when a
+	///! counter is stuck. None if there is no such run.
+	pub fn detect_plateau(&self, min_run: usize, tolerance: f64) -> Option<usize> {
+		if min_run == 0 || self.buckets.len() < min_run {
+			return None;
+		}
+		let is_level = |run: &[u64]| {
+			let max = *run.iter().max().unwrap_or(&0);
+			let min = *run.iter().min().unwrap_or(&0);
+			(max - min) as f64 <= max as f64 * tolerance / 100.0
+		};
+
+		let end = (min_run..=self.buckets.len())
+			.rev()
+			.find(|end| is_level(&self.buckets[end - min_run..*end]))?;
+		let mut start = end - min_run;
+		while start > 0 && is_level(&self.buckets[start - 1..end]) {
+			start -= 1;
+		}
+		Some(start)
+	}
+
+	///! Compare the mean of the newer half of the buckets with the older half
+	pub fn trend_direction(&self) -> TrendDirection {
+		let len = self.buckets.len();
+		if len < 4 {
+			return TrendDirection::Insufficient;
+		}
+
+		let half = len / 2;
+		let older: u64 = self.buckets[..half].iter().sum();
+		let newer: u64 = self.buckets[len - half..].iter().sum();
+		if older == 0 {
+			return if newer > 0 { TrendDirection::Rising } else { TrendDirection::Stable };
+		}
+
+		let ratio = newer as f64 / older as f64; // Halves are equal length so sums compare as means
+		if ratio > 1.1 {
+			TrendDirection::Rising
+		} else if ratio < 0.9 {
+			TrendDirection::Falling
+		} else {
+			TrendDirection::Stable
+		}
+	}
+
+	///! How far activity in other trails that in self, found by cross-correlation
+	///!
+	///! Positive if other trails self, negative if it leads, in multiples of bucket_duration.
+	///! None if either has fewer than 4 buckets or their bucket durations differ.
+	pub fn lag(&self, other: &BucketSet) -> Option<Duration> {
+		if self.buckets.len() < 4 || other.buckets.len() < 4 {
+			return None;
+		}
+		let (mine, theirs) = self.align_with(other).ok()?;
+		let centred = |buckets: Vec<u64>| {
+			let mean = buckets.iter().sum::<u64>() as f64 / buckets.len() as f64;
+			buckets.iter().map(|value| *value as f64 - mean).collect::<Vec<f64>>()
+		};
+		let (mine, theirs) = (centred(mine), centred(theirs));
+
+		// Mean product of the overlapping buckets when other is shifted back by offset
+		let len = mine.len() as i64;
+		let correlation = |offset: i64| {
+			let pairs = (0..len).filter(|i| i + offset >= 0 && i + offset < len);
+			let products: Vec<f64> = pairs.map(|i| mine[i as usize] * theirs[(i + offset) as usize]).collect();
+			products.iter().sum::<f64>() / products.len() as f64
+		};
+
+		// Search outwards from zero so that ties favour the smallest lag
+		let mut best = (0, correlation(0));
+		for distance in 1..=len / 2 {
+			for offset in [distance, -distance].iter() {
+				let value = correlation(*offset);
+				if value > best.1 {
+					best = (*offset, value);
+				}
+			}
+		}
+		Some(self.bucket_duration * best.0 as i32)
+	}
+
+	///! Estimate the value of the bucket which will follow the current one
+	pub fn predict_next(&self, method: PredictionMethod) -> u64 {
+		let len = self.buckets.len();
+		if len == 0 {
+			return 0;
+		}
+
+		match method {
+			PredictionMethod::LastValue => self.buckets[len - 1],
+			PredictionMethod::MovingAverage(window) => {
+				let window = window.max(1).min(len);
+				let sum: u64 = self.buckets[len - window..].iter().sum();
+				(sum as f64 / window as f64).round() as u64
+			}
+			PredictionMethod::LinearRegression => {
+				// Ordinary least squares fit of value against bucket index
+				let n = len as f64;
+				let mean_x = (n - 1.0) / 2.0;
+				let mean_y = self.buckets.iter().sum::<u64>() as f64 / n;
+				let mut covariance = 0.0;
+				let mut variance = 0.0;
+				for (x, y) in self.buckets.iter().enumerate() {
+					let dx = x as f64 - mean_x;
+					covariance += dx * (*y as f64 - mean_y);
+					variance += dx * dx;
+				}
+				let slope = if variance > 0.0 { covariance / variance } else { 0.0 };
+				let prediction = mean_y + slope * (n - mean_x);
+				if prediction > 0.0 {
+					prediction.round() as u64
+				} else {
+					0
+				}
+			}
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GapFillStrategy {
+	Hold,        // Repeat the value before the gap
+	Zero,        // Leave the gap as zeroes
+	Interpolate, // Bridge the values either side of the gap with a straight line
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TrendDirection {
+	Rising,
+	Falling,
+	Stable,
+	Insufficient, // Too few buckets to tell
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PredictionMethod {
+	LastValue,
+	LinearRegression,
+	MovingAverage(usize), // Mean of this number of most recent buckets
 }
 
 pub struct NodeMetrics {
 	pub node_started: Option<DateTime<Utc>>,
+	pub session_tags: Vec<String>, // From --tag
+	pub restarts: Vec<DateTime<Utc>>, // Shown as markers on the timeline
 	pub running_message: Option<String>,
 	pub running_version: Option<String>,
+	pub startup_steps: Vec<StartupStep>, // Progress towards 'Running sn_node' (see STARTUP_MESSAGES)
 	pub category_count: HashMap<String, usize>,
 	pub activity_history: Vec<ActivityEntry>,
 	pub log_history: Vec<LogEntry>,
@@ -740,6 +2657,23 @@ pub struct NodeMetrics {
 	pub puts_timeline: TimelineSet,
 	pub gets_timeline: TimelineSet,
 	pub errors_timeline: TimelineSet, // TODO add code to collect and display
+	pub disk_usage_timeline: TimelineSet,
+	pub memory_timeline: TimelineSet,
+	pub credits_timeline: TimelineSet,
+	pub consensus_timeline: TimelineSet,
+	pub sync_timeline: TimelineSet,
+	pub rate_limit_timeline: TimelineSet,
+	pub penalty_timeline: TimelineSet,
+	pub beacon_latency_timeline: TimelineSet,
+	pub connection_failure_timeline: TimelineSet,
+	pub transfers_timeline: TimelineSet,
+	pub bandwidth_in_timeline: TimelineSet,
+	pub bandwidth_out_timeline: TimelineSet,
+	pub gossip_timeline: TimelineSet,
+	pub shard_timeline: TimelineSet,
+	pub routing_timeline: TimelineSet,
+	pub cache_timeline: TimelineSet, // Cache lookups, hits and misses
+	pub timeout_timeline: TimelineSet,
 
 	pub most_recent: Option<DateTime<Utc>>,
 	pub agebracket: NodeAgebracket,
@@ -751,20 +2685,309 @@ pub struct NodeMetrics {
 	pub activity_gets: u64,
 	pub activity_puts: u64,
 	pub activity_errors: u64,
+	pub rate_limit_events: u64,
+	pub total_penalties: u64,
+	pub connection_failures: u64,
+	conn_failure_threshold: u64,
+	pub chunk_validations_passed: u64,
+	pub chunk_validations_failed: u64,
+	pub timeouts: u64,
+	pub lock_contention_events: u64,
+	pub max_lock_held_ms: u64,
+	pub sig_verifications: u64,
+	pub sig_failures: u64,
+	pub sig_avg_us: f64,
+	pub proofs_validated: u64,
+	pub proofs_rejected: u64,
+	proof_rejection_warned: bool, // See parse_data_proof()
+	pub cache_hits: u64,
+	pub cache_misses: u64,
+	pub pow_completions: u64,
+	pub pow_avg_ms: f64,
+	pub pow_avg_difficulty: f64,
+	pub credits_issued: u64,
+	pub token_transfers: Vec<TokenTransfer>,
+	pub banned_peers: HashMap<String, BanEvent>, // Most recent ban of each peer
+	pub total_transferred: u64,
+	pub received_bytes_total: u64,
+	pub sent_bytes_total: u64,
+	pub gossip_sent: u64,
+	pub gossip_received: u64,
+	pub shard_count: Option<usize>,
+	pub shard_count_delta: i64, // Change from the previous reading of shard_count
+	pub routing_additions: u64,
+	pub routing_removals: u64,
+	pub section_joins: u64, // In the last epoch reported
+	pub section_leaves: u64,
+	pub gets_by_type: HashMap<String, u64>,
+	pub puts_by_type: HashMap<String, u64>,
+	pub consensus_messages: HashMap<String, u64>, // Count by message type (e.g. "Vote")
+	pub disk_usage_bytes: Option<u64>,
+	pub quota_bytes: Option<u64>, // Storage allowed, from 'Storage quota' entries
+	pub used_bytes: Option<u64>,  // Storage used, from 'Storage quota' entries
+	disk_usage_baseline: Option<(DateTime<Utc>, u64)>, // For disk_usage_per_hour()
+	pub memory_usage_mb: Option<u64>,
+	pub avg_connection_duration_s: f64,
+	pub connection_durations: Vec<u64>, // Seconds, most recent last
+	pub beacon_responses: Vec<u64>,     // Milliseconds, most recent last
+
+	pub sections: Vec<SectionEntry>,
+	pub section_latencies: HashMap<String, Vec<u64>>, // Most recent timings (µs) by code section
+	pub task_durations: HashMap<String, Vec<u64>>, // Most recent durations (ms) by task name
+	pub index_update_times: Vec<u64>, // Most recent index update times (µs)
+	section_pattern: Regex,
+	pub elections: Vec<ElectionEvent>,
+	pub elections_count: u64,
+	pub replication_factor: Option<u8>,
+	min_replication: u8,
+	pub nat_type: Option<String>,
+	pub version_mismatches: u64,
+	pub mismatched_versions: Vec<String>, // Each peer version seen which differs from ours
+	pub protocol_version: Option<u8>,
+	pub protocol_version_history: Vec<(u8, Option<DateTime<Utc>>)>, // Each version negotiated and when
+	pub corruption_events: u64,
+	pub corruptions: Vec<CorruptionEvent>,
+	pub sync_events: Vec<SyncEvent>,
+	pub genesis_prefix: Option<String>,
+	pub genesis_prefix_changed: Option<DateTime<Utc>>, // Time of the last split or merge
+	pub quorum: bool,
+	pub quorum_votes: usize,
+	pub quorum_known: bool, // True once a quorum status has been logged
+
+	pub notifications: Vec<Notification>,
+	silence_notified: bool, // See check_silence()
+	disk_alert_bytes: Option<u64>,
+	memory_warn_mb: u64,
+	pub quota_warn_pct: u64,
+	pub index_warn_us: u64,
+	pub lock_warn_ms: u64,
 
 	pub debug_logfile: Option<NamedTempFile>,
 	parser_output: String,
 }
 
+///! A part of the logfile which begins with a banner line (see --section-pattern)
+pub struct SectionEntry {
+	pub name: String,
+	pub start: Option<DateTime<Utc>>,
+}
+
+static MAX_NOTIFICATIONS: usize = 20;
+static MAX_LATENCY_SAMPLES: usize = 1000; // For each code section and task
+static MAX_CONNECTION_DURATIONS: usize = 1000;
+static MAX_BEACON_RESPONSES: usize = 1000;
+static MIN_CHUNK_VALIDATION_RATE: f64 = 0.99; // Warn when the pass rate falls below this
+pub static HIGH_BEACON_LATENCY_MS: f64 = 500.0; // Shown as a warning above this
+pub static HIGH_ROUTING_CHURN_PER_MINUTE: f64 = 20.0; // Shown as a warning above this
+pub static HIGH_CHURN_RATIO: f64 = 0.5; // Leaves per join above which a section may be unstable
+pub static HIGH_POW_MS: f64 = 5000.0; // Average proof of work time suggesting an overloaded machine
+pub static MIN_CACHE_HIT_RATE: f64 = 0.5; // Below this the node may not have enough memory
+pub static MAX_SIG_FAILURE_RATE: f64 = 0.01; // Above this keys may be mismatched
+pub static MAX_PROOF_REJECTION_RATE: f64 = 0.01; // Above this stored data may be corrupt
+pub static HIGH_TIMEOUTS_PER_MINUTE: u64 = 10; // Timeouts at which health_score() loses the most
+
+lazy_static::lazy_static! {
+	static ref VERSION_MISMATCH_PATTERN: Regex =
+		Regex::new(r"Peer (?P<peer>\S+) running version (?P<version>\S+) \(expected (?P<expected>[^)]+)\)").expect("The regex failed to compile. This is a bug.");
+	static ref TIMING_PATTERN: Regex =
+		Regex::new(r"\[timing\] section=(?P<section>\S+) elapsed=(?P<elapsed>\d+)us").expect("The regex failed to compile. This is a bug.");
+	static ref PEER_ID_PATTERN: Regex =
+		Regex::new(r"(?i)\b(?:peer|from|to) (?P<peer>[0-9a-f]{6,})").expect("The regex failed to compile. This is a bug.");
+	static ref POW_PATTERN: Regex =
+		Regex::new(r"PoW completed in (?P<ms>\d+)ms with difficulty (?P<difficulty>\d+)").expect("The regex failed to compile. This is a bug.");
+	static ref INDEX_UPDATE_PATTERN: Regex =
+		Regex::new(r"Index updated: key=\S+ time=(?P<us>\d+)(µs|us)").expect("The regex failed to compile. This is a bug.");
+	static ref SIGNATURE_PATTERN: Regex =
+		Regex::new(r"Signature verified in (?P<us>\d+)(µs|us)").expect("The regex failed to compile. This is a bug.");
+	static ref LOCK_HELD_PATTERN: Regex =
+		Regex::new(r"Mutex held for (?P<ms>\d+)ms").expect("The regex failed to compile. This is a bug.");
+	static ref BAN_PATTERN: Regex =
+		Regex::new(r"Peer (?P<peer>\S+) banned for (?P<secs>\d+) seconds").expect("The regex failed to compile. This is a bug.");
+	static ref TASK_PATTERN: Regex =
+		Regex::new(r"Task completed: (?P<task>\S+) in (?P<ms>\d+)ms").expect("The regex failed to compile. This is a bug.");
+}
+
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub enum NotificationLevel {
+	Info,
+	Warning,
+	Critical,
+}
+
+///! Something noticed while parsing which the user should be told about
+pub struct Notification {
+	pub level: NotificationLevel,
+	pub time: Option<DateTime<Utc>>, // Logfile time
+	pub raised: DateTime<Utc>,       // Used to flash new notifications in the UI
+	pub message: String,
+}
+
+///! A current problem with a node (see App::health_check())
+pub struct HealthIssue {
+	pub monitor: String,
+	pub kind: HealthIssueKind,
+	pub message: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HealthIssueKind {
+	HighErrorRate,
+	StaleLog,
+	QuorumLost,
+	AnomalyDetected,
+	DiskNearFull,
+}
+
+impl HealthIssueKind {
+	///! How serious this kind of issue is
+	pub fn severity(&self) -> NotificationLevel {
+		match self {
+			HealthIssueKind::QuorumLost | HealthIssueKind::DiskNearFull => NotificationLevel::Critical,
+			HealthIssueKind::HighErrorRate | HealthIssueKind::StaleLog => NotificationLevel::Warning,
+			HealthIssueKind::AnomalyDetected => NotificationLevel::Info,
+		}
+	}
+}
+
+///! How to choose the most troubled node (see App::focus_monitor_by_health())
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HealthCriterion {
+	WorstHealthScore,
+	HighestErrorRate,
+	MostRecentAnomaly,
+}
+
+impl HealthCriterion {
+	///! How badly a node scores, higher being worse, or None if it doesn't qualify
+	pub fn badness(&self, metrics: &NodeMetrics) -> Option<f64> {
+		match self {
+			HealthCriterion::WorstHealthScore => Some(100.0 - metrics.health_score() as f64),
+			HealthCriterion::HighestErrorRate => {
+				let activity = metrics.activity_gets + metrics.activity_puts + metrics.activity_errors;
+				if activity == 0 {
+					return None;
+				}
+				Some(metrics.activity_errors as f64 / activity as f64)
+			}
+			// Notifications of warnings or worse, timed by when they were raised
+			HealthCriterion::MostRecentAnomaly => metrics
+				.notifications
+				.iter()
+				.filter(|notification| notification.level >= NotificationLevel::Warning)
+				.map(|notification| notification.raised.timestamp_millis() as f64)
+				.fold(None, |latest: Option<f64>, raised| Some(latest.map_or(raised, |latest| latest.max(raised)))),
+		}
+	}
+
+	pub fn description(&self) -> &'static str {
+		match self {
+			HealthCriterion::WorstHealthScore => "worst health score",
+			HealthCriterion::HighestErrorRate => "highest error rate",
+			HealthCriterion::MostRecentAnomaly => "most recent warning",
+		}
+	}
+}
+
+// Thresholds for LogMonitor::health_issues()
+static HEALTH_ERRORS_PER_MINUTE: u64 = 10;
+static HEALTH_STALE_LOG_MINUTES: i64 = 10;
+static HEALTH_DISK_FREE_PERCENT: u64 = 5;
+static HEALTH_ANOMALY_FACTOR: u64 = 4; // Activity this many times the recent average
+static HEALTH_ANOMALY_MINIMUM: u64 = 20; // ...and at least this much activity
+
+pub struct ElectionEvent {
+	pub round: u64,
+	pub time: Option<DateTime<Utc>>,
+}
+
+pub struct StartupStep {
+	pub name: String,
+	pub time: Option<DateTime<Utc>>,
+}
+
+///! Messages logged, in order, while a node initialises
+pub static STARTUP_MESSAGES: &[&str] = &[
+	"Loading routing tables",
+	"Bootstrapping",
+	"Connected to bootstrap nodes",
+];
+static STARTUP_COMPLETE: &str = "Running";
+
+///! A data synchronisation with a peer, which succeeds when 'Sync complete' is logged
+pub struct SyncEvent {
+	pub time: Option<DateTime<Utc>>,
+	pub peer: Option<String>,
+	pub success: bool,
+}
+
+///! Tokens moved between two accounts, from 'Token transfer' entries
+pub struct TokenTransfer {
+	pub amount: u64,
+	pub from: String,
+	pub to: String,
+	pub time: Option<DateTime<Utc>>,
+}
+
+///! A peer banned by the node, from 'Peer .. banned for' entries
+pub struct BanEvent {
+	pub duration_s: u64,
+	pub time: Option<DateTime<Utc>>,
+}
+
+pub struct CorruptionEvent {
+	pub time: Option<DateTime<Utc>>,
+	pub chunk_id: Option<String>,
+}
+
 impl NodeMetrics {
-	fn new(opt: &Opt) -> NodeMetrics {
+	fn new(config: &Config) -> NodeMetrics {
 		let mut puts_timeline = TimelineSet::new("PUTS".to_string());
 		let mut gets_timeline = TimelineSet::new("GETS".to_string());
 		let mut errors_timeline = TimelineSet::new("ERRORS".to_string());
-		for timeline in [&mut puts_timeline, &mut gets_timeline, &mut errors_timeline].iter_mut() {
+		let mut disk_usage_timeline = TimelineSet::new("DISK USAGE".to_string());
+		let mut memory_timeline = TimelineSet::new("MEMORY".to_string());
+		let mut credits_timeline = TimelineSet::new("CREDITS".to_string());
+		let mut consensus_timeline = TimelineSet::new("CONSENSUS".to_string());
+		let mut sync_timeline = TimelineSet::new("SYNC".to_string());
+		let mut rate_limit_timeline = TimelineSet::new("RATE LIMIT".to_string());
+		let mut penalty_timeline = TimelineSet::new("PENALTIES".to_string());
+		let mut beacon_latency_timeline = TimelineSet::new("BEACON LATENCY".to_string());
+		let mut connection_failure_timeline = TimelineSet::new("CONN FAILURES".to_string());
+		let mut transfers_timeline = TimelineSet::new("TRANSFERS".to_string());
+		let mut bandwidth_in_timeline = TimelineSet::new("BANDWIDTH IN".to_string());
+		let mut bandwidth_out_timeline = TimelineSet::new("BANDWIDTH OUT".to_string());
+		let mut gossip_timeline = TimelineSet::new("GOSSIP".to_string());
+		let mut shard_timeline = TimelineSet::new("SHARDS".to_string());
+		let mut routing_timeline = TimelineSet::new("ROUTING".to_string());
+		let mut cache_timeline = TimelineSet::new("CACHE".to_string());
+		let mut timeout_timeline = TimelineSet::new("TIMEOUTS".to_string());
+		for timeline in [
+			&mut puts_timeline,
+			&mut gets_timeline,
+			&mut errors_timeline,
+			&mut disk_usage_timeline,
+			&mut memory_timeline,
+			&mut credits_timeline,
+			&mut consensus_timeline,
+			&mut sync_timeline,
+			&mut rate_limit_timeline,
+			&mut penalty_timeline,
+			&mut beacon_latency_timeline,
+			&mut connection_failure_timeline,
+			&mut transfers_timeline,
+			&mut bandwidth_in_timeline,
+			&mut bandwidth_out_timeline,
+			&mut gossip_timeline,
+			&mut shard_timeline,
+			&mut routing_timeline,
+			&mut cache_timeline,
+			&mut timeout_timeline,
+		]
+		.iter_mut()
+		{
 			for i in 0..TIMELINES.len() {
 				if let Some(spec) = TIMELINES.get(i) {
-					timeline.add_bucket_set(spec.0, spec.1, opt.timeline_steps);
+					timeline.add_bucket_set(spec.0, spec.1, config.timeline_steps);
 				}
 			}
 		}
@@ -772,8 +2995,11 @@ impl NodeMetrics {
 		let mut metrics = NodeMetrics {
 			// Start
 			node_started: None,
+			session_tags: config.tags.clone(),
+			restarts: Vec::<DateTime<Utc>>::new(),
 			running_message: None,
 			running_version: None,
+			startup_steps: Vec::<StartupStep>::new(),
 
 			// Logfile entries
 			activity_history: Vec::<ActivityEntry>::new(),
@@ -784,12 +3010,66 @@ impl NodeMetrics {
 			puts_timeline,
 			gets_timeline,
 			errors_timeline,
+			disk_usage_timeline,
+			memory_timeline,
+			credits_timeline,
+			consensus_timeline,
+			sync_timeline,
+			rate_limit_timeline,
+			penalty_timeline,
+			beacon_latency_timeline,
+			connection_failure_timeline,
+			transfers_timeline,
+			bandwidth_in_timeline,
+			bandwidth_out_timeline,
+			gossip_timeline,
+			shard_timeline,
+			routing_timeline,
+			cache_timeline,
+			timeout_timeline,
 
 			// Counts
 			category_count: HashMap::new(),
 			activity_gets: 0,
 			activity_puts: 0,
 			activity_errors: 0,
+			rate_limit_events: 0,
+			total_penalties: 0,
+			connection_failures: 0,
+			conn_failure_threshold: config.conn_failure_threshold,
+			chunk_validations_passed: 0,
+			chunk_validations_failed: 0,
+			timeouts: 0,
+			lock_contention_events: 0,
+			max_lock_held_ms: 0,
+			sig_verifications: 0,
+			sig_failures: 0,
+			sig_avg_us: 0.0,
+			proofs_validated: 0,
+			proofs_rejected: 0,
+			proof_rejection_warned: false,
+			cache_hits: 0,
+			cache_misses: 0,
+			pow_completions: 0,
+			pow_avg_ms: 0.0,
+			pow_avg_difficulty: 0.0,
+			credits_issued: 0,
+			token_transfers: Vec::<TokenTransfer>::new(),
+			banned_peers: HashMap::new(),
+			total_transferred: 0,
+			received_bytes_total: 0,
+			sent_bytes_total: 0,
+			gossip_sent: 0,
+			gossip_received: 0,
+			shard_count: None,
+			shard_count_delta: 0,
+			routing_additions: 0,
+			routing_removals: 0,
+			section_joins: 0,
+			section_leaves: 0,
+			gets_by_type: HashMap::new(),
+			puts_by_type: HashMap::new(),
+			consensus_messages: HashMap::new(),
 
 			// State (node)
 			agebracket: NodeAgebracket::Unknown,
@@ -801,6 +3081,65 @@ impl NodeMetrics {
 			adults: 0,
 			elders: 0,
 
+			// State (resources)
+			disk_usage_bytes: None,
+			quota_bytes: None,
+			used_bytes: None,
+			disk_usage_baseline: None,
+			memory_usage_mb: None,
+			avg_connection_duration_s: 0.0,
+			connection_durations: Vec::<u64>::new(),
+			beacon_responses: Vec::<u64>::new(),
+
+			// Sections
+			section_latencies: HashMap::new(),
+			task_durations: HashMap::new(),
+			index_update_times: Vec::<u64>::new(),
+			sections: Vec::<SectionEntry>::new(),
+			section_pattern: config.section_pattern.clone(),
+
+			// Elections
+			elections: Vec::<ElectionEvent>::new(),
+			elections_count: 0,
+
+			// Replication
+			replication_factor: None,
+			min_replication: config.min_replication,
+
+			// Connectivity
+			nat_type: None,
+
+			// Peer versions
+			version_mismatches: 0,
+			mismatched_versions: Vec::<String>::new(),
+			protocol_version: None,
+			protocol_version_history: Vec::new(),
+
+			// Data corruption
+			corruption_events: 0,
+			corruptions: Vec::<CorruptionEvent>::new(),
+
+			// Data synchronisation
+			sync_events: Vec::<SyncEvent>::new(),
+
+			// Network prefix
+			genesis_prefix: None,
+			genesis_prefix_changed: None,
+
+			// Quorum
+			quorum: false,
+			quorum_votes: 0,
+			quorum_known: false,
+
+			// Alerts
+			notifications: Vec::<Notification>::new(),
+			silence_notified: false,
+			disk_alert_bytes: config.disk_alert_gb.map(|gb| (gb * 1e9) as u64),
+			memory_warn_mb: config.memory_warn_mb,
+			quota_warn_pct: config.quota_warn_pct,
+			index_warn_us: config.index_warn_us,
+			lock_warn_ms: config.lock_warn_ms,
+
 			// Debug
 			debug_logfile: None,
 			parser_output: String::from("-"),
@@ -809,6 +3148,13 @@ impl NodeMetrics {
 		metrics
 	}
 
+	///! Add a tag describing this monitoring session (see --tag)
+	pub fn push_tag(&mut self, s: String) {
+		if !self.session_tags.contains(&s) {
+			self.session_tags.push(s);
+		}
+	}
+
 	pub fn agebracket_string(&self) -> String {
 		match self.agebracket {
 			NodeAgebracket::Infant => "Infant".to_string(),
@@ -828,6 +3174,9 @@ impl NodeMetrics {
 		self.activity_gets = 0;
 		self.activity_puts = 0;
 		self.activity_errors = 0;
+		self.total_penalties = 0;
+		self.gets_by_type.clear();
+		self.puts_by_type.clear();
 	}
 
 	///! Process a line from a SAFE Node logfile.
@@ -836,20 +3185,13 @@ impl NodeMetrics {
 	pub fn gather_metrics(&mut self, line: &str) -> Result<(), std::io::Error> {
 		// For debugging LogEntry::decode()
 		let mut parser_result = format!("LogEntry::decode() failed on: {}", line);
-		if let Some(mut entry) = LogEntry::decode(line).or_else(|| self.parse_start(line)) {
-			if entry.time.is_none() {
-				entry.time = self.most_recent;
-			} else {
-				self.most_recent = entry.time;
-			}
-
-			self.update_timelines(self.most_recent);
-			self.parser_output = entry.parser_output.clone();
-			self.process_logfile_entry(&entry); // May overwrite self.parser_output
+		if let Some(entry) = LogEntry::decode(line).or_else(|| self.parse_start(line)) {
+			self.add_entry(entry);
 			parser_result = self.parser_output.clone();
-			self.log_history.push(entry);
+		}
 
-			// TODO Trim log_history
+		if self.parse_section_header(line) || self.parse_section_latencies(line) {
+			parser_result = self.parser_output.clone();
 		}
 
 		// --debug-dashboard - prints parser results for a single logfile
@@ -859,89 +3201,574 @@ impl NodeMetrics {
 		Ok(())
 	}
 
-	pub fn update_timelines(&mut self, now: Option<DateTime<Utc>>) {
-		for timeline in &mut [
-			&mut self.puts_timeline,
-			&mut self.gets_timeline,
-			&mut self.errors_timeline,
-		]
-		.iter_mut()
-		{
-			timeline.update_current_time(now);
+	///! Update metrics from a LogEntry and add it to the log_history
+	fn add_entry(&mut self, mut entry: LogEntry) {
+		if entry.time.is_none() {
+			entry.time = self.most_recent;
+		} else {
+			self.most_recent = entry.time;
 		}
-	}
 
-	///! Returm a LogEntry and capture metadata for logfile node start:
-	///!	'Running safe-node v0.24.0'
-	pub fn parse_start(&mut self, line: &str) -> Option<LogEntry> {
-		let running_prefix = String::from("Running sn_node ");
+		self.update_timelines(self.most_recent);
+		self.parser_output = entry.parser_output.clone();
+		self.process_logfile_entry(&entry); // May overwrite self.parser_output
+		self.log_history.push(entry);
 
-		if line.starts_with(&running_prefix) {
-			self.running_message = Some(line.to_string());
-			self.running_version = Some(line[running_prefix.len()..].to_string());
-			self.node_started = self.most_recent;
-			let parser_output = format!(
-				"START at {}",
-				self.most_recent
-					.map_or(String::from("None"), |m| format!("{}", m))
-			);
+		// TODO Trim log_history
+	}
 
-			self.reset_metrics();
-			return Some(LogEntry {
-				logstring: String::from(line),
-				category: String::from("START"),
-				time: self.most_recent,
-				source: String::from(""),
-				message: line.to_string(),
-				parser_output,
-			});
+	///! Clear everything gathered from logfile entries, ready to process them again
+	fn reset_history(&mut self) {
+		self.reset_metrics();
+		self.most_recent = None;
+		self.restarts.clear();
+		self.category_count.clear();
+		self.activity_history.clear();
+		self.log_history.clear();
+		self.elections.clear();
+		self.elections_count = 0;
+		self.quorum = false;
+		self.quorum_votes = 0;
+		self.quorum_known = false;
+		self.replication_factor = None;
+		self.nat_type = None;
+		self.version_mismatches = 0;
+		self.mismatched_versions.clear();
+		self.protocol_version = None;
+		self.protocol_version_history.clear();
+		self.corruption_events = 0;
+		self.corruptions.clear();
+		self.sync_events.clear();
+		self.genesis_prefix = None;
+		self.genesis_prefix_changed = None;
+		self.notifications.clear();
+		self.disk_usage_bytes = None;
+		self.quota_bytes = None;
+		self.used_bytes = None;
+		self.disk_usage_baseline = None;
+		self.memory_usage_mb = None;
+		self.avg_connection_duration_s = 0.0;
+		self.connection_durations.clear();
+		self.beacon_responses.clear();
+		self.credits_issued = 0;
+		self.rate_limit_events = 0;
+		self.total_penalties = 0;
+		self.connection_failures = 0;
+		self.chunk_validations_passed = 0;
+		self.chunk_validations_failed = 0;
+		self.timeouts = 0;
+		self.lock_contention_events = 0;
+		self.max_lock_held_ms = 0;
+		self.sig_verifications = 0;
+		self.sig_failures = 0;
+		self.sig_avg_us = 0.0;
+		self.proofs_validated = 0;
+		self.proofs_rejected = 0;
+		self.proof_rejection_warned = false;
+		self.cache_hits = 0;
+		self.cache_misses = 0;
+		self.pow_completions = 0;
+		self.pow_avg_ms = 0.0;
+		self.pow_avg_difficulty = 0.0;
+		self.index_update_times.clear();
+		self.task_durations.clear();
+		self.startup_steps.clear();
+		self.token_transfers.clear();
+		self.banned_peers.clear();
+		self.total_transferred = 0;
+		self.received_bytes_total = 0;
+		self.sent_bytes_total = 0;
+		self.gossip_sent = 0;
+		self.gossip_received = 0;
+		self.shard_count = None;
+		self.shard_count_delta = 0;
+		self.routing_additions = 0;
+		self.routing_removals = 0;
+		self.section_joins = 0;
+		self.section_leaves = 0;
+		self.consensus_messages.clear();
+		for timeline in self.timelines_mut() {
+			timeline.reset();
 		}
-
-		None
 	}
 
-	///! Process a logfile entry
-	///! Returns true if the line has been processed and can be discarded
-	pub fn process_logfile_entry(&mut self, entry: &LogEntry) -> bool {
-		return self.parse_data_response(
-			&entry,
-			"Running as Node: SendToSection [ msg: MsgEnvelope { message: QueryResponse { response: QueryResponse::",
-		) || self.parse_gets_and_puts(&entry) || self.parse_states(&entry);
+	pub fn shift_time(&mut self, delta: Duration) {
+		for timeline in self.timelines_mut() {
+			timeline.shift_time(delta);
+		}
 	}
 
-	///! TODO: Review and update these tests
-	///! TODO: see forum conversation https://safenetforum.org/t/vdash-safe-node-dashboard-safe-vault-run-baby-fleming-t/32630/38
-	fn parse_gets_and_puts(&mut self, entry: &LogEntry) -> bool {
-		if entry.message.contains("Handling NodeDuty: ReadChunk") {
-			self.count_get(entry.time);
-			return true;
-		} else if entry.message.contains("Wrote data from message") {
-			self.count_put(entry.time);
-			return true;
-			// TODO: delete the following checks once the new test network is out
-		} else if entry.message.contains("Writing chunk succeeded") {
-			self.count_put(entry.time);
-			return true;
-		} else if entry.message.starts_with("MapStorage: Writing chunk PASSED") {
-			self.count_put(entry.time);
-			return true;
+	pub fn set_timeline_steps(&mut self, timeline_steps: usize) {
+		for timeline in self.timelines_mut() {
+			timeline.set_max_buckets(timeline_steps);
 		}
-		return false;
 	}
 
-	///! Update data metrics from a handler response logfile entry
-	///! Returns true if the line has been processed and can be discarded
-	fn parse_data_response(&mut self, entry: &LogEntry, pattern: &str) -> bool {
-		if let Some(mut response_start) = entry.logstring.find(pattern) {
-			response_start += pattern.len();
-			let mut response = "";
+	///! Rebuild all metrics from the log_history (see LogMonitor::reparse_history())
+	pub fn reparse_history(&mut self) {
+		let history = std::mem::replace(&mut self.log_history, Vec::<LogEntry>::new());
+		self.reset_history();
+		for entry in history {
+			if entry.category.eq("START") {
+				self.parse_start(&entry.logstring);
+			}
+			self.add_entry(entry);
+		}
+		self.update_timelines(Some(Utc::now()));
+	}
 
-			if let Some(response_end) = entry.logstring[response_start..].find(",") {
+	///! Add the counts and timelines of other to these metrics (see App::merge_monitors())
+	pub fn merge_from(&mut self, other: &NodeMetrics) {
+		self.activity_gets += other.activity_gets;
+		self.activity_puts += other.activity_puts;
+		self.activity_errors += other.activity_errors;
+		self.rate_limit_events += other.rate_limit_events;
+		self.total_penalties += other.total_penalties;
+		self.connection_failures += other.connection_failures;
+		self.chunk_validations_passed += other.chunk_validations_passed;
+		self.chunk_validations_failed += other.chunk_validations_failed;
+		self.timeouts += other.timeouts;
+		self.lock_contention_events += other.lock_contention_events;
+		self.max_lock_held_ms = self.max_lock_held_ms.max(other.max_lock_held_ms);
+		let sig_verifications = self.sig_verifications + other.sig_verifications;
+		if sig_verifications > 0 {
+			let weight = other.sig_verifications as f64 / sig_verifications as f64;
+			self.sig_avg_us += (other.sig_avg_us - self.sig_avg_us) * weight;
+		}
+		self.sig_verifications = sig_verifications;
+		self.sig_failures += other.sig_failures;
+		self.proofs_validated += other.proofs_validated;
+		self.proofs_rejected += other.proofs_rejected;
+		self.cache_hits += other.cache_hits;
+		self.cache_misses += other.cache_misses;
+		let pow_completions = self.pow_completions + other.pow_completions;
+		if pow_completions > 0 {
+			let weight = other.pow_completions as f64 / pow_completions as f64;
+			self.pow_avg_ms += (other.pow_avg_ms - self.pow_avg_ms) * weight;
+			self.pow_avg_difficulty += (other.pow_avg_difficulty - self.pow_avg_difficulty) * weight;
+		}
+		self.pow_completions = pow_completions;
+		self.index_update_times.extend(other.index_update_times.iter());
+		self.credits_issued += other.credits_issued;
+		self.total_transferred += other.total_transferred;
+		self.received_bytes_total += other.received_bytes_total;
+		self.sent_bytes_total += other.sent_bytes_total;
+		self.gossip_sent += other.gossip_sent;
+		self.gossip_received += other.gossip_received;
+		self.routing_additions += other.routing_additions;
+		self.routing_removals += other.routing_removals;
+		self.section_joins += other.section_joins;
+		self.section_leaves += other.section_leaves;
+		for transfer in other.token_transfers.iter() {
+			self.token_transfers.push(TokenTransfer {
+				amount: transfer.amount,
+				from: transfer.from.clone(),
+				to: transfer.to.clone(),
+				time: transfer.time,
+			});
+		}
+		for (peer, ban) in other.banned_peers.iter() {
+			if self.banned_peers.get(peer).map_or(true, |existing| existing.time < ban.time) {
+				self.banned_peers.insert(peer.clone(), BanEvent { duration_s: ban.duration_s, time: ban.time });
+			}
+		}
+		self.version_mismatches += other.version_mismatches;
+		for version in other.mismatched_versions.iter() {
+			if !self.mismatched_versions.contains(version) {
+				self.mismatched_versions.push(version.clone());
+			}
+		}
+		self.corruption_events += other.corruption_events;
+		for corruption in other.corruptions.iter() {
+			self.corruptions.push(CorruptionEvent {
+				time: corruption.time,
+				chunk_id: corruption.chunk_id.clone(),
+			});
+		}
+		self.corruptions.sort_by_key(|corruption| corruption.time); // So the latest is last
+		self.elections_count += other.elections_count;
+		if let Some(bytes) = other.disk_usage_bytes {
+			self.disk_usage_bytes = Some(self.disk_usage_bytes.unwrap_or(0) + bytes);
+		}
+		if let (Some(quota), Some(used)) = (other.quota_bytes, other.used_bytes) {
+			self.quota_bytes = Some(self.quota_bytes.unwrap_or(0) + quota);
+			self.used_bytes = Some(self.used_bytes.unwrap_or(0) + used);
+		}
+		for (category, count) in other.category_count.iter() {
+			*self.category_count.entry(category.clone()).or_insert(0) += count;
+		}
+		for (data_type, count) in other.gets_by_type.iter() {
+			*self.gets_by_type.entry(data_type.clone()).or_insert(0) += count;
+		}
+		for (data_type, count) in other.puts_by_type.iter() {
+			*self.puts_by_type.entry(data_type.clone()).or_insert(0) += count;
+		}
+		for (message_type, count) in other.consensus_messages.iter() {
+			*self.consensus_messages.entry(message_type.clone()).or_insert(0) += count;
+		}
+
+		self.puts_timeline.merge_from(&other.puts_timeline);
+		self.gets_timeline.merge_from(&other.gets_timeline);
+		self.errors_timeline.merge_from(&other.errors_timeline);
+		self.disk_usage_timeline.merge_from(&other.disk_usage_timeline);
+		self.memory_timeline.merge_from(&other.memory_timeline);
+		self.credits_timeline.merge_from(&other.credits_timeline);
+		self.consensus_timeline.merge_from(&other.consensus_timeline);
+		self.sync_timeline.merge_from(&other.sync_timeline);
+		self.rate_limit_timeline.merge_from(&other.rate_limit_timeline);
+		self.penalty_timeline.merge_from(&other.penalty_timeline);
+		self.beacon_latency_timeline.merge_from(&other.beacon_latency_timeline);
+		self.connection_failure_timeline.merge_from(&other.connection_failure_timeline);
+		self.transfers_timeline.merge_from(&other.transfers_timeline);
+		self.bandwidth_in_timeline.merge_from(&other.bandwidth_in_timeline);
+		self.bandwidth_out_timeline.merge_from(&other.bandwidth_out_timeline);
+		self.gossip_timeline.merge_from(&other.gossip_timeline);
+		self.shard_timeline.merge_from(&other.shard_timeline);
+		self.routing_timeline.merge_from(&other.routing_timeline);
+		self.cache_timeline.merge_from(&other.cache_timeline);
+		self.timeout_timeline.merge_from(&other.timeout_timeline);
+
+		if other.most_recent > self.most_recent {
+			self.most_recent = other.most_recent;
+		}
+	}
+
+	///! Start a new section if the line is a banner such as:
+	///!	'====== Node starting up ======'
+	fn parse_section_header(&mut self, line: &str) -> bool {
+		if let Some(captures) = self.section_pattern.captures(line) {
+			let name = captures
+				.get(1)
+				.or(captures.get(0))
+				.map_or("", |m| m.as_str())
+				.to_string();
+			self.parser_output = format!("SECTION: {}", &name);
+			self.sections.push(SectionEntry {
+				name,
+				start: self.most_recent,
+			});
+			return true;
+		}
+		false
+	}
+
+	///! Capture timings of code sections from:
+	///!	'[timing] section=routing elapsed=1234us'
+	fn parse_section_latencies(&mut self, line: &str) -> bool {
+		if let Some(captures) = TIMING_PATTERN.captures(line) {
+			if let Ok(elapsed) = captures["elapsed"].parse::<u64>() {
+				let section = captures["section"].to_string();
+				let timings = self.section_latencies.entry(section.clone()).or_insert_with(Vec::new);
+				timings.push(elapsed);
+				if timings.len() > MAX_LATENCY_SAMPLES {
+					timings.remove(0);
+				}
+				self.parser_output = format!("TIMING: {} {}us", section, elapsed);
+				return true;
+			}
+		}
+		false
+	}
+
+	///! 99th percentile of the recent timings of a code section, in µs
+	pub fn section_p99(&self, name: &str) -> Option<u64> {
+		percentile(self.section_latencies.get(name)?, 99)
+	}
+
+	///! Capture the name of this node from:
+	///!	'Our name: 8a3f2c1b..'
+	fn parse_node_name(&mut self, entry: &LogEntry) -> bool {
+		if let Some(name) = self.parse_word("Our name:", &entry.message) {
+			self.parser_output = format!("node name: {}", &name);
+			self.node_name = name;
+			return true;
+		}
+		false
+	}
+
+	///! True if peer_id (lowercase) refers to this node, allowing for either being abbreviated
+	pub fn is_named(&self, peer_id: &str) -> bool {
+		let name = self.node_name.trim_end_matches('.').to_lowercase();
+		if name.is_empty() || peer_id.is_empty() {
+			return false;
+		}
+		name.starts_with(peer_id) || peer_id.starts_with(name.as_str())
+	}
+
+	///! Capture the network protocol version from:
+	///!	'Protocol version negotiated: v5'
+	fn parse_protocol_version(&mut self, entry: &LogEntry) -> bool {
+		if let Some(word) = self.parse_word("Protocol version negotiated:", &entry.message) {
+			if let Ok(version) = word.trim_start_matches('v').parse::<u8>() {
+				if self.protocol_version == Some(version) {
+					return true;
+				}
+				if let Some(previous) = self.protocol_version {
+					self.push_notification(
+						NotificationLevel::Info,
+						format!("Protocol version changed from v{} to v{}", previous, version),
+					);
+				}
+				self.protocol_version = Some(version);
+				self.protocol_version_history.push((version, entry.time));
+				self.parser_output = format!("PROTOCOL VERSION: v{}", version);
+				return true;
+			}
+		}
+		false
+	}
+
+	///! Capture how long async tasks take from:
+	///!	'Task completed: handle_message in 12ms'
+	fn parse_task_duration(&mut self, entry: &LogEntry) -> bool {
+		if let Some(captures) = TASK_PATTERN.captures(&entry.message) {
+			if let Ok(ms) = captures["ms"].parse::<u64>() {
+				let task = captures["task"].to_string();
+				let durations = self.task_durations.entry(task.clone()).or_insert_with(Vec::new);
+				durations.push(ms);
+				if durations.len() > MAX_LATENCY_SAMPLES {
+					durations.remove(0);
+				}
+				self.parser_output = format!("TASK: {} {}ms", task, ms);
+				return true;
+			}
+		}
+		false
+	}
+
+	///! Capture the time to update the node's index from:
+	///!	'Index updated: key=8a3f2c time=350µs'
+	fn parse_index_update(&mut self, entry: &LogEntry) -> bool {
+		if let Some(captures) = INDEX_UPDATE_PATTERN.captures(&entry.message) {
+			if let Ok(us) = captures["us"].parse::<u64>() {
+				let was_over = self.index_p99_us().map_or(false, |p99| p99 > self.index_warn_us);
+				self.index_update_times.push(us);
+				if self.index_update_times.len() > MAX_LATENCY_SAMPLES {
+					self.index_update_times.remove(0);
+				}
+				let p99 = self.index_p99_us().unwrap_or(0);
+				if p99 > self.index_warn_us && !was_over {
+					self.push_notification(
+						NotificationLevel::Warning,
+						format!("Index update P99 {}µs is over {}µs", p99, self.index_warn_us),
+					);
+				}
+				self.parser_output = format!("INDEX UPDATE: {}µs", us);
+				return true;
+			}
+		}
+		false
+	}
+
+	///! Median of the recent index update times, in µs
+	pub fn index_p50_us(&self) -> Option<u64> {
+		percentile(&self.index_update_times, 50)
+	}
+
+	///! 99th percentile of the recent index update times, in µs
+	pub fn index_p99_us(&self) -> Option<u64> {
+		percentile(&self.index_update_times, 99)
+	}
+
+	///! Median of the recent durations of a task, in ms
+	pub fn task_p50(&self, name: &str) -> Option<u64> {
+		percentile(self.task_durations.get(name)?, 50)
+	}
+
+	///! 95th percentile of the recent durations of a task, in ms
+	pub fn task_p95(&self, name: &str) -> Option<u64> {
+		percentile(self.task_durations.get(name)?, 95)
+	}
+
+	pub fn is_section_header(&self, line: &str) -> bool {
+		self.section_pattern.is_match(line)
+	}
+
+	///! Every TimelineSet, for changes which apply to them all
+	pub fn timelines(&self) -> Vec<&TimelineSet> {
+		vec![
+			&self.puts_timeline,
+			&self.gets_timeline,
+			&self.errors_timeline,
+			&self.disk_usage_timeline,
+			&self.memory_timeline,
+			&self.credits_timeline,
+			&self.consensus_timeline,
+			&self.sync_timeline,
+			&self.rate_limit_timeline,
+			&self.penalty_timeline,
+			&self.beacon_latency_timeline,
+			&self.connection_failure_timeline,
+			&self.transfers_timeline,
+			&self.bandwidth_in_timeline,
+			&self.bandwidth_out_timeline,
+			&self.gossip_timeline,
+			&self.shard_timeline,
+			&self.routing_timeline,
+			&self.cache_timeline,
+			&self.timeout_timeline,
+		]
+	}
+
+	fn timelines_mut(&mut self) -> Vec<&mut TimelineSet> {
+		vec![
+			&mut self.puts_timeline,
+			&mut self.gets_timeline,
+			&mut self.errors_timeline,
+			&mut self.disk_usage_timeline,
+			&mut self.memory_timeline,
+			&mut self.credits_timeline,
+			&mut self.consensus_timeline,
+			&mut self.sync_timeline,
+			&mut self.rate_limit_timeline,
+			&mut self.penalty_timeline,
+			&mut self.beacon_latency_timeline,
+			&mut self.connection_failure_timeline,
+			&mut self.transfers_timeline,
+			&mut self.bandwidth_in_timeline,
+			&mut self.bandwidth_out_timeline,
+			&mut self.gossip_timeline,
+			&mut self.shard_timeline,
+			&mut self.routing_timeline,
+			&mut self.cache_timeline,
+			&mut self.timeout_timeline,
+		]
+	}
+
+	pub fn update_timelines(&mut self, now: Option<DateTime<Utc>>) {
+		for timeline in self.timelines_mut() {
+			timeline.update_current_time(now);
+		}
+	}
+
+	///! Classify recent activity of the named timeline (e.g. "PUTS") at the given scale (e.g. "minute")
+	pub fn trend_direction(&self, timeline_name: &str, scale: &str) -> TrendDirection {
+		let timeline = self
+			.timelines()
+			.into_iter()
+			.find(|timeline| timeline.get_name().eq_ignore_ascii_case(timeline_name))
+			.and_then(|timeline| timeline.get_bucket_set(scale));
+
+		match timeline {
+			Some(bucket_set) => bucket_set.trend_direction(),
+			None => TrendDirection::Insufficient,
+		}
+	}
+
+	///! Returm a LogEntry and capture metadata for logfile node start:
+	///!	'Running safe-node v0.24.0'
+	pub fn parse_start(&mut self, line: &str) -> Option<LogEntry> {
+		let running_prefix = String::from("Running sn_node ");
+
+		if line.starts_with(&running_prefix) {
+			self.running_message = Some(line.to_string());
+			self.running_version = Some(line[running_prefix.len()..].to_string());
+			self.node_started = self.most_recent;
+			if self.startup_in_progress() {
+				self.startup_steps.push(StartupStep {
+					name: STARTUP_COMPLETE.to_string(),
+					time: self.most_recent,
+				});
+			}
+			if let Some(time) = self.most_recent {
+				self.restarts.push(time);
+			}
+			let parser_output = format!(
+				"START at {}",
+				self.most_recent
+					.map_or(String::from("None"), |m| format!("{}", m))
+			);
+
+			self.reset_metrics();
+			return Some(LogEntry {
+				logstring: String::from(line),
+				category: String::from("START"),
+				time: self.most_recent,
+				source: String::from(""),
+				message: line.to_string(),
+				parser_output,
+			});
+		}
+
+		None
+	}
+
+	///! Process a logfile entry
+	///! Returns true if the line has been processed and can be discarded
+	pub fn process_logfile_entry(&mut self, entry: &LogEntry) -> bool {
+		return self.parse_data_response(
+			&entry,
+			"Running as Node: SendToSection [ msg: MsgEnvelope { message: QueryResponse { response: QueryResponse::",
+		) || self.parse_gets_and_puts(&entry)
+			|| self.parse_states(&entry)
+			|| self.parse_election_event(&entry)
+			|| self.parse_quorum_status(&entry)
+			|| self.parse_genesis_prefix(&entry)
+			|| self.parse_storage_credit(&entry)
+			|| self.parse_version_mismatch(&entry)
+			|| self.parse_protocol_version(&entry)
+			|| self.parse_consensus_message(&entry)
+			|| self.parse_data_corruption(&entry)
+			|| self.parse_sync_event(&entry)
+			|| self.parse_startup_sequence(&entry)
+			|| self.parse_nat_type(&entry)
+			|| self.parse_rate_limit(&entry)
+			|| self.parse_penalty_event(&entry)
+			|| self.parse_beacon_response(&entry)
+			|| self.parse_connection_failure(&entry)
+			|| self.parse_chunk_validation(&entry)
+			|| self.parse_pow(&entry)
+			|| self.parse_cache_lookup(&entry)
+			|| self.parse_signature_verification(&entry)
+			|| self.parse_data_proof(&entry)
+			|| self.parse_lock_contention(&entry)
+			|| self.parse_timeout(&entry)
+			|| self.parse_index_update(&entry)
+			|| self.parse_token_transfer(&entry)
+			|| self.parse_peer_ban(&entry)
+			|| self.parse_message_size(&entry)
+			|| self.parse_gossip(&entry)
+			|| self.parse_shard_count(&entry)
+			|| self.parse_routing_change(&entry)
+			|| self.parse_section_churn(&entry)
+			|| self.parse_task_duration(&entry)
+			|| self.parse_node_name(&entry);
+	}
+
+	///! TODO: Review and update these tests
+	///! TODO: see forum conversation https://safenetforum.org/t/vdash-safe-node-dashboard-safe-vault-run-baby-fleming-t/32630/38
+	fn parse_gets_and_puts(&mut self, entry: &LogEntry) -> bool {
+		if entry.message.contains("Handling NodeDuty: ReadChunk") {
+			self.count_get(entry.time);
+			return true;
+		} else if entry.message.contains("Wrote data from message") {
+			self.count_put(entry.time);
+			return true;
+			// TODO: delete the following checks once the new test network is out
+		} else if entry.message.contains("Writing chunk succeeded") {
+			self.count_put(entry.time);
+			return true;
+		} else if entry.message.starts_with("MapStorage: Writing chunk PASSED") {
+			self.count_put(entry.time);
+			return true;
+		}
+		return false;
+	}
+
+	///! Update data metrics from a handler response logfile entry
+	///! Returns true if the line has been processed and can be discarded
+	fn parse_data_response(&mut self, entry: &LogEntry, pattern: &str) -> bool {
+		if let Some(mut response_start) = entry.logstring.find(pattern) {
+			response_start += pattern.len();
+			let mut response = "";
+
+			if let Some(response_end) = entry.logstring[response_start..].find(",") {
 				response = entry.logstring.as_str()[response_start..response_start + response_end]
 					.as_ref();
 				if !response.is_empty() {
 					let activity_entry = ActivityEntry::new(entry, response);
 					self.activity_history.push(activity_entry);
+					self.parse_data_types(response);
 					self.parser_output = format!("node activity: {}", response);
 				}
 			}
@@ -954,6 +3781,31 @@ impl NodeMetrics {
 		return false;
 	}
 
+	///! Count a data response by its type token, such as 'GetImmutableData' or 'MutateChunk'
+	///! Returns true if the response was recognised as a GET or PUT
+	fn parse_data_types(&mut self, response: &str) -> bool {
+		let data_type = match response.find(|c: char| !c.is_alphanumeric()) {
+			Some(end) => &response[..end],
+			None => response,
+		};
+		if data_type.is_empty() {
+			return false;
+		}
+
+		let counts = if data_type.starts_with("Get") {
+			&mut self.gets_by_type
+		} else if data_type.starts_with("Mutate")
+			|| data_type.starts_with("Put")
+			|| data_type.starts_with("Store")
+		{
+			&mut self.puts_by_type
+		} else {
+			return false;
+		};
+		*counts.entry(data_type.to_string()).or_insert(0) += 1;
+		true
+	}
+
 	///! Capture state updates from a logfile entry
 	///! Returns true if the line has been processed and can be discarded
 	fn parse_states(&mut self, entry: &LogEntry) -> bool {
@@ -961,6 +3813,15 @@ impl NodeMetrics {
 			self.count_error(entry.time);
 		}
 
+		if self.parse_disk_usage(entry)
+			|| self.parse_memory_usage(entry)
+			|| self.parse_connection_duration(entry)
+			|| self.parse_replication_factor(entry)
+			|| self.parse_storage_quota(entry)
+		{
+			return true;
+		}
+
 		let &content = &entry.logstring.as_str();
 		if let Some(elders) = self.parse_usize("No. of Elders:", content) {
 			self.elders = elders;
@@ -1041,7 +3902,946 @@ impl NodeMetrics {
 			return true;
 		}
 
-		false
+		false
+	}
+
+	///! Capture storage used by the node from:
+	///!	'Current disk usage: 1234567 bytes'
+	fn parse_disk_usage(&mut self, entry: &LogEntry) -> bool {
+		if let Some(bytes) = self.parse_u64("Current disk usage:", &entry.logstring) {
+			let previous_bytes = self.disk_usage_bytes.unwrap_or(0);
+			self.disk_usage_bytes = Some(bytes);
+			self.disk_usage_timeline.set_value(entry.time, bytes);
+			if self.disk_usage_baseline.is_none() {
+				if let Some(time) = entry.time {
+					self.disk_usage_baseline = Some((time, bytes));
+				}
+			}
+			self.parser_output = format!("DISK USAGE: {} bytes", bytes);
+
+			if let Some(alert_bytes) = self.disk_alert_bytes {
+				if bytes > alert_bytes && previous_bytes <= alert_bytes {
+					self.push_notification(
+						NotificationLevel::Warning,
+						format!("Disk usage {} bytes exceeds alert level", bytes),
+					);
+				}
+			}
+			return true;
+		}
+		false
+	}
+
+	///! Capture the storage quota of the node and how much of it is used from:
+	///!	'Storage quota: 100 GB (used: 42.5 GB)'
+	fn parse_storage_quota(&mut self, entry: &LogEntry) -> bool {
+		let prefix = "Storage quota:";
+		let position = match entry.message.find(prefix) {
+			Some(position) => position,
+			None => return false,
+		};
+		let content = &entry.message[position + prefix.len()..];
+		let gb = |text: &str| text.trim().split_whitespace().next().and_then(|n| n.parse::<f64>().ok());
+		let quota_gb = content.split('(').next().and_then(gb);
+		let used_gb = content.find("used:").and_then(|start| gb(&content[start + 5..]));
+		if let (Some(quota_gb), Some(used_gb)) = (quota_gb, used_gb) {
+			let was_over = self.quota_utilization().map_or(false, |utilization| utilization * 100.0 > self.quota_warn_pct as f64);
+			self.quota_bytes = Some((quota_gb * 1e9) as u64);
+			self.used_bytes = Some((used_gb * 1e9) as u64);
+			self.parser_output = format!("STORAGE QUOTA: {} GB (used: {} GB)", quota_gb, used_gb);
+
+			if let Some(utilization) = self.quota_utilization() {
+				if utilization * 100.0 > self.quota_warn_pct as f64 && !was_over {
+					self.push_notification(
+						NotificationLevel::Warning,
+						format!("Storage quota {:.0}% used, over {}%", utilization * 100.0, self.quota_warn_pct),
+					);
+				}
+			}
+			return true;
+		}
+		false
+	}
+
+	///! Fraction of the storage quota used, or None if unknown (or the quota is zero)
+	pub fn quota_utilization(&self) -> Option<f64> {
+		match (self.used_bytes, self.quota_bytes) {
+			(Some(used), Some(quota)) if quota > 0 => Some(used as f64 / quota as f64),
+			_ => None,
+		}
+	}
+
+	///! Capture heap allocation reported by the node from:
+	///!	'Current memory usage: 300 MB'
+	fn parse_memory_usage(&mut self, entry: &LogEntry) -> bool {
+		if let Some(mb) = self.parse_u64("Current memory usage:", &entry.logstring) {
+			let previous_mb = self.memory_usage_mb.unwrap_or(0);
+			self.memory_usage_mb = Some(mb);
+			self.memory_timeline.set_value(entry.time, mb);
+			self.parser_output = format!("MEMORY USAGE: {} MB", mb);
+
+			if mb > self.memory_warn_mb && previous_mb <= self.memory_warn_mb {
+				self.push_notification(
+					NotificationLevel::Warning,
+					format!("Memory usage {} MB exceeds {} MB", mb, self.memory_warn_mb),
+				);
+			}
+			return true;
+		}
+		false
+	}
+
+	///! Labels marking node events (such as restarts) on the buckets of bucket_set
+	pub fn event_annotations(&self, bucket_set: &BucketSet) -> Vec<Option<String>> {
+		let annotations: Vec<(usize, String)> = self
+			.restarts
+			.iter()
+			.filter_map(|time| bucket_set.index_for_time(*time))
+			.map(|index| (index, "restart".to_string()))
+			.collect();
+		bucket_set.annotate(&annotations)
+	}
+
+	///! Capture the number of copies kept of each chunk from:
+	///!	'Replication factor: 4'
+	fn parse_replication_factor(&mut self, entry: &LogEntry) -> bool {
+		if let Some(factor) = self.parse_usize("Replication factor:", &entry.message) {
+			let factor = factor.min(u8::MAX as usize) as u8;
+			let was_below = self.replication_factor.map_or(false, |previous| previous < self.min_replication);
+			if factor < self.min_replication && !was_below {
+				self.push_notification(
+					NotificationLevel::Critical,
+					format!("Replication factor {} is below {}", factor, self.min_replication),
+				);
+			}
+			self.replication_factor = Some(factor);
+			self.parser_output = format!("REPLICATION FACTOR: {}", factor);
+			return true;
+		}
+		false
+	}
+
+	///! Overall health of the node from 0 (failing) to 100 (no problems seen)
+	pub fn health_score(&self) -> u8 {
+		if self.replication_factor.map_or(false, |factor| factor < self.min_replication) {
+			return 0;
+		}
+
+		let mut score: i64 = 100;
+		if self.quorum_known && !self.quorum {
+			score -= 50;
+		}
+		if self.version_mismatches > 0 {
+			score -= 10;
+		}
+		if self.corruption_events > 0 {
+			score -= 30;
+		}
+		if self.connection_failures > 0 {
+			let failures = self.connection_failures.min(self.conn_failure_threshold);
+			score -= (20 * failures / self.conn_failure_threshold.max(1)) as i64; // Up to 20 for failures
+		}
+		if self.timeouts > 0 {
+			let per_minute = self.timeouts_per_minute().min(HIGH_TIMEOUTS_PER_MINUTE);
+			score -= (15 * per_minute / HIGH_TIMEOUTS_PER_MINUTE) as i64; // Up to 15 for timeouts
+		}
+		if self.pow_completions > 0 && self.pow_avg_ms > HIGH_POW_MS {
+			score -= 10; // Proof of work is slow, so the machine may be overloaded
+		}
+		let activity = self.activity_gets + self.activity_puts + self.activity_errors;
+		if activity > 0 {
+			score -= (40 * self.activity_errors / activity) as i64; // Up to 40 for errors
+		}
+		score.max(0) as u8
+	}
+
+	///! Health scores after each of the last window entries of log_history, oldest first
+	///!
+	///! This replays only the errors and connection failures in the window, so unlike
+	///! health_score() it ignores quorum, version, corruption and replication problems.
+	pub fn compute_score_history(&self, window: usize) -> Vec<u8> {
+		let start = self.log_history.len().saturating_sub(window);
+		let mut errors: u64 = 0;
+		let mut failures: u64 = 0;
+		let threshold = self.conn_failure_threshold.max(1);
+		self.log_history[start..]
+			.iter()
+			.enumerate()
+			.map(|(index, entry)| {
+				if entry.category.eq("ERROR") {
+					errors += 1;
+				}
+				if entry.message.contains("Connection refused by peer")
+					|| entry.message.contains("Failed to connect to bootstrap node")
+				{
+					failures += 1;
+				}
+				let mut score: i64 = 100;
+				score -= (40 * errors / (index as u64 + 1)) as i64;
+				score -= (20 * failures.min(threshold) / threshold) as i64;
+				score.max(0) as u8
+			})
+			.collect()
+	}
+
+	///! Raise a critical notification once when there has been no logfile entry
+	///! for longer than timeout, and again if the node goes silent after recovering
+	pub fn check_silence(&mut self, now: DateTime<Utc>, timeout: Duration) {
+		let silent = match self.most_recent {
+			Some(most_recent) => now - most_recent > timeout,
+			None => false,
+		};
+		if silent && !self.silence_notified {
+			self.push_notification(
+				NotificationLevel::Critical,
+				format!("No logfile entries for over {}s", timeout.num_seconds()),
+			);
+		}
+		self.silence_notified = silent;
+	}
+
+	///! Capture how long a peer stayed connected from:
+	///!	'Peer disconnected after 95 seconds'
+	fn parse_connection_duration(&mut self, entry: &LogEntry) -> bool {
+		if let Some(seconds) = self.parse_u64("Peer disconnected after", &entry.message) {
+			// Running mean over the retained durations
+			if self.connection_durations.len() >= MAX_CONNECTION_DURATIONS {
+				let oldest = self.connection_durations.remove(0);
+				self.connection_durations.push(seconds);
+				let count = self.connection_durations.len() as f64;
+				self.avg_connection_duration_s += (seconds as f64 - oldest as f64) / count;
+			} else {
+				self.connection_durations.push(seconds);
+				let count = self.connection_durations.len() as f64;
+				self.avg_connection_duration_s += (seconds as f64 - self.avg_connection_duration_s) / count;
+			}
+			self.parser_output = format!("CONNECTION DURATION: {}s", seconds);
+			return true;
+		}
+		false
+	}
+
+	pub fn max_connection_duration(&self) -> Option<u64> {
+		self.connection_durations.iter().max().cloned()
+	}
+
+	///! Capture the latency of responses to the bootstrap beacon from:
+	///!	'Beacon response from 8a3f2c.. in 120ms'
+	fn parse_beacon_response(&mut self, entry: &LogEntry) -> bool {
+		let prefix = "Beacon response from";
+		if let Some(position) = entry.message.find(prefix) {
+			let content = &entry.message[position + prefix.len()..];
+			let latency = content
+				.rfind(" in ")
+				.and_then(|start| content[start + 4..].trim().strip_suffix("ms"))
+				.and_then(|ms| ms.parse::<u64>().ok());
+			if let Some(ms) = latency {
+				if self.beacon_responses.len() >= MAX_BEACON_RESPONSES {
+					self.beacon_responses.remove(0);
+				}
+				self.beacon_responses.push(ms);
+				self.beacon_latency_timeline.set_value(entry.time, ms);
+				self.parser_output = format!("BEACON LATENCY: {}ms", ms);
+				return true;
+			}
+		}
+		false
+	}
+
+	///! Capture failures to connect to other nodes from:
+	///!	'Connection refused by peer 8a3f2c..'
+	///!	'Failed to connect to bootstrap node 1b2c3d..'
+	fn parse_connection_failure(&mut self, entry: &LogEntry) -> bool {
+		if entry.message.contains("Connection refused by peer")
+			|| entry.message.contains("Failed to connect to bootstrap node")
+		{
+			self.connection_failures += 1;
+			self.connection_failure_timeline.increment_value(entry.time);
+			self.parser_output = format!("CONNECTION FAILURES: {}", self.connection_failures);
+			return true;
+		}
+		false
+	}
+
+	///! Capture the result of validating a stored chunk from:
+	///!	'Chunk validation passed'
+	///!	'Chunk validation failed'
+	fn parse_chunk_validation(&mut self, entry: &LogEntry) -> bool {
+		if entry.message.contains("Chunk validation passed") {
+			self.chunk_validations_passed += 1;
+		} else if entry.message.contains("Chunk validation failed") {
+			let was_low = self.chunk_validation_rate().map_or(false, |rate| rate < MIN_CHUNK_VALIDATION_RATE);
+			self.chunk_validations_failed += 1;
+			let rate = self.chunk_validation_rate().unwrap_or(0.0);
+			if rate < MIN_CHUNK_VALIDATION_RATE && !was_low {
+				self.push_notification(
+					NotificationLevel::Warning,
+					format!("Chunk validation pass rate has fallen to {:.1}%", rate * 100.0),
+				);
+			}
+		} else {
+			return false;
+		}
+		self.parser_output = format!(
+			"CHUNK VALIDATION: {} passed, {} failed",
+			self.chunk_validations_passed, self.chunk_validations_failed
+		);
+		true
+	}
+
+	///! Capture timeouts from:
+	///!	'Request timed out after 5000ms'
+	///!	'Operation timeout: GetChunk'
+	fn parse_timeout(&mut self, entry: &LogEntry) -> bool {
+		if entry.message.contains("Request timed out after") || entry.message.contains("Operation timeout:") {
+			self.timeouts += 1;
+			self.timeout_timeline.increment_value(entry.time);
+			self.parser_output = format!("TIMEOUTS: {}", self.timeouts);
+			return true;
+		}
+		false
+	}
+
+	///! Number of timeouts in the last complete minute
+	pub fn timeouts_per_minute(&self) -> u64 {
+		last_minute_count(&self.timeout_timeline)
+	}
+
+	///! Capture lock contention from:
+	///!	'Lock contention detected for mutex: chunk_store'
+	///!	'Mutex held for 250ms'
+	fn parse_lock_contention(&mut self, entry: &LogEntry) -> bool {
+		if entry.message.contains("Lock contention detected for mutex:") {
+			self.lock_contention_events += 1;
+			self.parser_output = format!("LOCK CONTENTION: {}", self.lock_contention_events);
+			return true;
+		}
+		if let Some(captures) = LOCK_HELD_PATTERN.captures(&entry.message) {
+			if let Ok(ms) = captures["ms"].parse::<u64>() {
+				if ms > self.lock_warn_ms && self.max_lock_held_ms <= self.lock_warn_ms {
+					self.push_notification(
+						NotificationLevel::Warning,
+						format!("Mutex held for {}ms, over {}ms", ms, self.lock_warn_ms),
+					);
+				}
+				self.max_lock_held_ms = self.max_lock_held_ms.max(ms);
+				self.parser_output = format!("LOCK HELD: {}ms", ms);
+				return true;
+			}
+		}
+		false
+	}
+
+	///! Capture signature checks from:
+	///!	'Signature verified in 85µs'
+	///!	'Signature verification failed'
+	fn parse_signature_verification(&mut self, entry: &LogEntry) -> bool {
+		if let Some(captures) = SIGNATURE_PATTERN.captures(&entry.message) {
+			if let Ok(us) = captures["us"].parse::<u64>() {
+				// Running mean over every verification
+				self.sig_verifications += 1;
+				self.sig_avg_us += (us as f64 - self.sig_avg_us) / self.sig_verifications as f64;
+				self.parser_output = format!("SIGNATURE VERIFIED: {}µs", us);
+				return true;
+			}
+		} else if entry.message.contains("Signature verification failed") {
+			self.sig_failures += 1;
+			self.parser_output = format!("SIGNATURE FAILURES: {}", self.sig_failures);
+			return true;
+		}
+		false
+	}
+
+	///! Proportion of signature checks which failed, or None before any are seen
+	pub fn sig_failure_rate(&self) -> Option<f64> {
+		let total = self.sig_verifications + self.sig_failures;
+		if total == 0 {
+			return None;
+		}
+		Some(self.sig_failures as f64 / total as f64)
+	}
+
+	///! Capture data proofs from:
+	///!	'Data proof validated'
+	///!	'Data proof rejected'
+	fn parse_data_proof(&mut self, entry: &LogEntry) -> bool {
+		if entry.message.contains("Data proof validated") {
+			self.proofs_validated += 1;
+		} else if entry.message.contains("Data proof rejected") {
+			self.proofs_rejected += 1;
+		} else {
+			return false;
+		}
+		let rejection_rate = 1.0 - self.proof_acceptance_rate().unwrap_or(1.0);
+		if rejection_rate > MAX_PROOF_REJECTION_RATE && !self.proof_rejection_warned {
+			self.proof_rejection_warned = true;
+			self.push_notification(
+				NotificationLevel::Warning,
+				format!("{:.1}% of data proofs rejected", rejection_rate * 100.0),
+			);
+		}
+		self.parser_output = format!("DATA PROOFS: {} validated, {} rejected", self.proofs_validated, self.proofs_rejected);
+		true
+	}
+
+	///! Proportion of data proofs which were validated, or None before any are seen
+	pub fn proof_acceptance_rate(&self) -> Option<f64> {
+		let total = self.proofs_validated + self.proofs_rejected;
+		if total == 0 {
+			return None;
+		}
+		Some(self.proofs_validated as f64 / total as f64)
+	}
+
+	///! Capture cache lookups from:
+	///!	'Cache hit: key=8a3f2c'
+	///!	'Cache miss: key=8a3f2c'
+	fn parse_cache_lookup(&mut self, entry: &LogEntry) -> bool {
+		if entry.message.contains("Cache hit:") {
+			self.cache_hits += 1;
+		} else if entry.message.contains("Cache miss:") {
+			self.cache_misses += 1;
+		} else {
+			return false;
+		}
+		self.cache_timeline.increment_value(entry.time);
+		self.parser_output = format!("CACHE: {} hits, {} misses", self.cache_hits, self.cache_misses);
+		true
+	}
+
+	///! Proportion of cache lookups which were hits, or None before any are seen
+	pub fn cache_hit_rate(&self) -> Option<f64> {
+		let total = self.cache_hits + self.cache_misses;
+		if total == 0 {
+			return None;
+		}
+		Some(self.cache_hits as f64 / total as f64)
+	}
+
+	///! Capture proof of work timings from:
+	///!	'PoW completed in 1200ms with difficulty 18'
+	fn parse_pow(&mut self, entry: &LogEntry) -> bool {
+		if let Some(captures) = POW_PATTERN.captures(&entry.message) {
+			let (ms, difficulty) = match (captures["ms"].parse::<u64>(), captures["difficulty"].parse::<u64>()) {
+				(Ok(ms), Ok(difficulty)) => (ms, difficulty),
+				_ => return false,
+			};
+			// Running means over every completion
+			self.pow_completions += 1;
+			let count = self.pow_completions as f64;
+			self.pow_avg_ms += (ms as f64 - self.pow_avg_ms) / count;
+			self.pow_avg_difficulty += (difficulty as f64 - self.pow_avg_difficulty) / count;
+			self.parser_output = format!("POW: {}ms difficulty {}", ms, difficulty);
+			return true;
+		}
+		false
+	}
+
+	///! Proportion of chunk validations which passed, or None before any are seen
+	pub fn chunk_validation_rate(&self) -> Option<f64> {
+		let total = self.chunk_validations_passed + self.chunk_validations_failed;
+		if total == 0 {
+			return None;
+		}
+		Some(self.chunk_validations_passed as f64 / total as f64)
+	}
+
+	///! True when connection failures exceed --conn-failure-threshold
+	pub fn connection_failures_high(&self) -> bool {
+		self.connection_failures > self.conn_failure_threshold
+	}
+
+	///! Mean latency of the retained beacon responses, in milliseconds
+	pub fn avg_beacon_latency_ms(&self) -> Option<f64> {
+		if self.beacon_responses.is_empty() {
+			return None;
+		}
+		let total: u64 = self.beacon_responses.iter().sum();
+		Some(total as f64 / self.beacon_responses.len() as f64)
+	}
+
+	///! Average change in disk usage since the first reading, in bytes per hour
+	pub fn disk_usage_per_hour(&self) -> Option<f64> {
+		if let (Some((start_time, start_bytes)), Some(bytes), Some(now)) =
+			(self.disk_usage_baseline, self.disk_usage_bytes, self.most_recent)
+		{
+			let seconds = (now - start_time).num_seconds();
+			if seconds > 0 {
+				return Some((bytes as f64 - start_bytes as f64) * 3600.0 / seconds as f64);
+			}
+		}
+		None
+	}
+
+	///! Capture the start of an elder election from:
+	///!	'Elder election round 7 started'
+	fn parse_election_event(&mut self, entry: &LogEntry) -> bool {
+		if !entry.message.contains("started") {
+			return false;
+		}
+		if let Some(round) = self.parse_u64("Elder election round", &entry.message) {
+			self.elections.push(ElectionEvent {
+				round,
+				time: entry.time,
+			});
+			self.elections_count += 1;
+			self.parser_output = format!("ELECTION round: {}", round);
+			self.push_notification(
+				NotificationLevel::Info,
+				format!("Elder election round {} started", round),
+			);
+			return true;
+		}
+		false
+	}
+
+	///! Capture peers running a different version from:
+	///!	'Peer 8a3f2c.. running version v0.23.0 (expected v0.24.0)'
+	fn parse_version_mismatch(&mut self, entry: &LogEntry) -> bool {
+		if let Some(captures) = VERSION_MISMATCH_PATTERN.captures(&entry.message) {
+			let version = captures["version"].to_string();
+			self.version_mismatches += 1;
+			self.parser_output = format!("VERSION MISMATCH: {}", version);
+			self.push_notification(
+				NotificationLevel::Warning,
+				format!(
+					"Peer {} running {} (expected {})",
+					&captures["peer"], version, &captures["expected"]
+				),
+			);
+			if !self.mismatched_versions.contains(&version) {
+				self.mismatched_versions.push(version);
+			}
+			return true;
+		}
+		false
+	}
+
+	///! Capture checksum failures and other corrupted data from:
+	///!	'Checksum failed for chunk 8a3f2c..'
+	///!	'Data corruption detected'
+	fn parse_data_corruption(&mut self, entry: &LogEntry) -> bool {
+		let chunk_id = if entry.message.contains("Checksum failed for chunk") {
+			self.parse_word("Checksum failed for chunk", &entry.message)
+				.map(|word| word.trim_end_matches(|c| c == ':' || c == '.').to_string())
+				.filter(|word| !word.is_empty())
+		} else if entry.message.contains("Data corruption detected") {
+			None
+		} else {
+			return false;
+		};
+
+		self.corruption_events += 1;
+		let message = match &chunk_id {
+			Some(chunk_id) => format!("Data corruption in chunk {}", chunk_id),
+			None => String::from("Data corruption detected"),
+		};
+		self.parser_output = format!("CORRUPTION: {}", chunk_id.as_deref().unwrap_or("-"));
+		self.push_notification(NotificationLevel::Critical, message);
+		self.corruptions.push(CorruptionEvent {
+			time: entry.time,
+			chunk_id,
+		});
+		true
+	}
+
+	///! Capture each step of node initialisation (see STARTUP_MESSAGES) from:
+	///!	'Loading routing tables'
+	///!	'Bootstrapping'
+	///!	'Connected to bootstrap nodes'
+	fn parse_startup_sequence(&mut self, entry: &LogEntry) -> bool {
+		let name = match STARTUP_MESSAGES.iter().find(|message| entry.message.contains(*message)) {
+			Some(name) => name.to_string(),
+			None => return false,
+		};
+		if name == STARTUP_MESSAGES[0] {
+			self.startup_steps.clear(); // Starting again
+		}
+		self.parser_output = format!("STARTUP: {}", name);
+		self.startup_steps.push(StartupStep {
+			name,
+			time: entry.time,
+		});
+		true
+	}
+
+	///! True between the first startup step and 'Running sn_node'
+	pub fn startup_in_progress(&self) -> bool {
+		match self.startup_steps.last() {
+			Some(step) => step.name != STARTUP_COMPLETE,
+			None => false,
+		}
+	}
+
+	///! Fraction of the startup steps completed, from 0.0 to 1.0
+	pub fn startup_progress(&self) -> f64 {
+		let steps = STARTUP_MESSAGES.len() + 1; // Including 'Running sn_node'
+		(self.startup_steps.len() as f64 / steps as f64).min(1.0)
+	}
+
+	///! Capture the NAT (or firewall) type of the node from:
+	///!	'NAT type: FullCone'
+	fn parse_nat_type(&mut self, entry: &LogEntry) -> bool {
+		if let Some(nat_type) = self.parse_word("NAT type:", &entry.message) {
+			if nat_type == "Symmetric" && self.nat_type.as_deref() != Some("Symmetric") {
+				self.push_notification(
+					NotificationLevel::Warning,
+					String::from("Symmetric NAT may prevent peers connecting to this node"),
+				);
+			}
+			self.parser_output = format!("NAT type: {}", nat_type);
+			self.nat_type = Some(nat_type);
+			return true;
+		}
+		false
+	}
+
+	///! Capture synchronisation of data with other nodes from:
+	///!	'Synchronising data with peer 8a3f2c..'
+	///!	'Sync complete'
+	fn parse_sync_event(&mut self, entry: &LogEntry) -> bool {
+		if entry.message.contains("Synchronising data with peer") {
+			let peer = self.parse_word("Synchronising data with peer", &entry.message);
+			self.parser_output = format!("SYNC with: {}", peer.as_deref().unwrap_or("-"));
+			self.sync_events.push(SyncEvent {
+				time: entry.time,
+				peer,
+				success: false,
+			});
+			self.sync_timeline.increment_value(entry.time);
+			return true;
+		}
+
+		if entry.message.contains("Sync complete") {
+			self.parser_output = String::from("SYNC complete");
+			match self.sync_events.iter_mut().rev().find(|sync| !sync.success) {
+				Some(sync) => sync.success = true,
+				None => {
+					self.sync_events.push(SyncEvent {
+						time: entry.time,
+						peer: None,
+						success: true,
+					});
+					self.sync_timeline.increment_value(entry.time);
+				}
+			}
+			return true;
+		}
+		false
+	}
+
+	///! Proportion of synchronisations which completed, or None before any are seen
+	pub fn sync_success_rate(&self) -> Option<f64> {
+		if self.sync_events.is_empty() {
+			return None;
+		}
+		let successes = self.sync_events.iter().filter(|sync| sync.success).count();
+		Some(successes as f64 / self.sync_events.len() as f64)
+	}
+
+	///! Capture tokens moved between accounts from:
+	///!	'Token transfer: 25 tokens from 8a3f2c.. to 1b2c3d..'
+	fn parse_token_transfer(&mut self, entry: &LogEntry) -> bool {
+		let amount = match self.parse_u64("Token transfer:", &entry.message) {
+			Some(amount) => amount,
+			None => return false,
+		};
+		let accounts = match entry.message.find(" from ") {
+			Some(position) => &entry.message[position..],
+			None => return false,
+		};
+		let from = self.parse_word(" from ", accounts);
+		let to = self.parse_word(" to ", accounts);
+		if let (Some(from), Some(to)) = (from, to) {
+			self.total_transferred += amount;
+			self.transfers_timeline.add_value(entry.time, amount);
+			self.parser_output = format!("TOKEN TRANSFER: {} from {} to {}", amount, from, to);
+			self.token_transfers.push(TokenTransfer { amount, from, to, time: entry.time });
+			return true;
+		}
+		false
+	}
+
+	///! Capture peers banned by the node from:
+	///!	'Peer 8a3f2c.. banned for 600 seconds'
+	fn parse_peer_ban(&mut self, entry: &LogEntry) -> bool {
+		if let Some(captures) = BAN_PATTERN.captures(&entry.message) {
+			if let Ok(duration_s) = captures["secs"].parse::<u64>() {
+				let peer = captures["peer"].to_string();
+				self.push_notification(
+					NotificationLevel::Warning,
+					format!("Peer {} banned for {} seconds", peer, duration_s),
+				);
+				self.parser_output = format!("BANNED: {} for {}s", peer, duration_s);
+				self.banned_peers.insert(peer, BanEvent { duration_s, time: entry.time });
+				return true;
+			}
+		}
+		false
+	}
+
+	///! Number of bans which have not expired by the most recent entry
+	pub fn active_bans(&self) -> usize {
+		self.banned_peers
+			.values()
+			.filter(|ban| match (ban.time, self.most_recent) {
+				(Some(time), Some(most_recent)) => time + Duration::seconds(ban.duration_s as i64) > most_recent,
+				_ => true,
+			})
+			.count()
+	}
+
+	///! Capture the size of messages received and sent from:
+	///!	'Received message of 1024 bytes'
+	///!	'Sent message of 512 bytes'
+	fn parse_message_size(&mut self, entry: &LogEntry) -> bool {
+		if let Some(bytes) = self.parse_u64("Received message of", &entry.message) {
+			self.received_bytes_total += bytes;
+			self.bandwidth_in_timeline.add_value(entry.time, bytes);
+			self.parser_output = format!("RECEIVED: {} bytes", bytes);
+			return true;
+		}
+		if let Some(bytes) = self.parse_u64("Sent message of", &entry.message) {
+			self.sent_bytes_total += bytes;
+			self.bandwidth_out_timeline.add_value(entry.time, bytes);
+			self.parser_output = format!("SENT: {} bytes", bytes);
+			return true;
+		}
+		false
+	}
+
+	///! Capture gossip between nodes from:
+	///!	'Gossip message sent to 5 peers'
+	///!	'Gossip received from peer 8a3f2c..'
+	fn parse_gossip(&mut self, entry: &LogEntry) -> bool {
+		if entry.message.contains("Gossip message sent to") {
+			self.gossip_sent += 1;
+		} else if entry.message.contains("Gossip received from") {
+			self.gossip_received += 1;
+		} else {
+			return false;
+		}
+		self.gossip_timeline.increment_value(entry.time);
+		self.parser_output = format!("GOSSIP: {} sent, {} received", self.gossip_sent, self.gossip_received);
+		true
+	}
+
+	///! Number of gossip messages sent and received in the last complete minute
+	pub fn gossip_per_minute(&self) -> u64 {
+		last_minute_count(&self.gossip_timeline)
+	}
+
+	///! Capture changes to the routing table from:
+	///!	'Added 3 to routing table'
+	///!	'Removed 1 from routing table'
+	fn parse_routing_change(&mut self, entry: &LogEntry) -> bool {
+		if !entry.message.contains("routing table") {
+			return false;
+		}
+		if let Some(count) = self.parse_u64("Added", &entry.message) {
+			self.routing_additions += count;
+			self.routing_timeline.add_value(entry.time, count);
+		} else if let Some(count) = self.parse_u64("Removed", &entry.message) {
+			self.routing_removals += count;
+			self.routing_timeline.add_value(entry.time, count);
+		} else {
+			return false;
+		}
+		self.parser_output = format!(
+			"ROUTING: {} added, {} removed",
+			self.routing_additions, self.routing_removals
+		);
+		true
+	}
+
+	///! Routing table additions plus removals in the last complete minute,
+	///! or None if the routing table has not changed
+	pub fn routing_churn_rate(&self) -> Option<f64> {
+		if self.routing_additions + self.routing_removals == 0 {
+			return None;
+		}
+		Some(last_minute_count(&self.routing_timeline) as f64)
+	}
+
+	///! Capture section membership changes in the last epoch from:
+	///!	'Section churn: 4 joins, 1 leaves in last epoch'
+	fn parse_section_churn(&mut self, entry: &LogEntry) -> bool {
+		if let Some(joins) = self.parse_u64("Section churn:", &entry.message) {
+			if let Some(leaves) = self.parse_u64("joins,", &entry.message) {
+				self.section_joins = joins;
+				self.section_leaves = leaves;
+				self.parser_output = format!("SECTION CHURN: {} joins, {} leaves", joins, leaves);
+				return true;
+			}
+		}
+		false
+	}
+
+	///! Section leaves per join, or 0.0 when there were no joins
+	pub fn churn_ratio(&self) -> f64 {
+		if self.section_joins == 0 {
+			return 0.0;
+		}
+		self.section_leaves as f64 / self.section_joins as f64
+	}
+
+	///! Capture the number of shards in the network from:
+	///!	'Currently 12 shards in network'
+	fn parse_shard_count(&mut self, entry: &LogEntry) -> bool {
+		if !entry.message.contains("shards in network") {
+			return false;
+		}
+		if let Some(count) = self.parse_usize("Currently", &entry.message) {
+			if let Some(previous) = self.shard_count {
+				self.shard_count_delta = count as i64 - previous as i64;
+			}
+			self.shard_count = Some(count);
+			self.shard_timeline.set_value(entry.time, count as u64);
+			self.parser_output = format!("SHARDS: {} ({:+})", count, self.shard_count_delta);
+			return true;
+		}
+		false
+	}
+
+	///! Bytes received per second over the last complete minute
+	pub fn bandwidth_in_per_second(&self) -> f64 {
+		self.bandwidth_in_timeline
+			.get_bucket_set("1 minute columns")
+			.map_or(0.0, |bucket_set| bucket_set.rate_per_second())
+	}
+
+	///! Bytes sent per second over the last complete minute
+	pub fn bandwidth_out_per_second(&self) -> f64 {
+		self.bandwidth_out_timeline
+			.get_bucket_set("1 minute columns")
+			.map_or(0.0, |bucket_set| bucket_set.rate_per_second())
+	}
+
+	///! Capture credit paid to the node from:
+	///!	'Credit: 100 tokens to 8a3f2c..'
+	fn parse_storage_credit(&mut self, entry: &LogEntry) -> bool {
+		if !entry.message.contains("tokens to") {
+			return false;
+		}
+		if let Some(tokens) = self.parse_u64("Credit:", &entry.message) {
+			self.credits_issued += tokens;
+			self.credits_timeline.add_value(entry.time, tokens);
+			self.parser_output = format!("CREDIT: {} tokens", tokens);
+			return true;
+		}
+		false
+	}
+
+	///! Count consensus messages by type from:
+	///!	'Consensus msg received: Propose'
+	fn parse_consensus_message(&mut self, entry: &LogEntry) -> bool {
+		if let Some(message_type) = self.parse_word("Consensus msg received:", &entry.message) {
+			*self.consensus_messages.entry(message_type.clone()).or_insert(0) += 1;
+			self.consensus_timeline.increment_value(entry.time);
+			self.parser_output = format!("CONSENSUS: {}", message_type);
+			return true;
+		}
+		false
+	}
+
+	///! Number of consensus messages received in the last complete minute
+	pub fn consensus_per_minute(&self) -> u64 {
+		last_minute_count(&self.consensus_timeline)
+	}
+
+	///! Capture requests refused because of rate limiting from:
+	///!	'Rate limit exceeded for client 8a3f2c..'
+	///!	'Request throttled'
+	fn parse_rate_limit(&mut self, entry: &LogEntry) -> bool {
+		if entry.message.contains("Rate limit exceeded") || entry.message.contains("Request throttled") {
+			self.rate_limit_events += 1;
+			self.rate_limit_timeline.increment_value(entry.time);
+			self.parser_output = format!("RATE LIMIT: {}", self.rate_limit_events);
+			return true;
+		}
+		false
+	}
+
+	///! Number of requests rate limited in the last complete minute
+	pub fn throttle_per_minute(&self) -> u64 {
+		last_minute_count(&self.rate_limit_timeline)
+	}
+
+	///! Capture penalties applied to the node for misbehaviour from:
+	///!	'Penalty applied: 10 points'
+	fn parse_penalty_event(&mut self, entry: &LogEntry) -> bool {
+		if let Some(points) = self.parse_u64("Penalty applied:", &entry.message) {
+			self.total_penalties += points;
+			self.penalty_timeline.add_value(entry.time, points);
+			self.push_notification(
+				NotificationLevel::Warning,
+				format!("Penalty of {} points applied (total {})", points, self.total_penalties),
+			);
+			self.parser_output = format!("PENALTY: {} (total {})", points, self.total_penalties);
+			return true;
+		}
+		false
+	}
+
+	///! Capture the network prefix of the node from:
+	///!	'Our prefix: 01011'
+	///! This is logged at startup and again after a section split or merge
+	fn parse_genesis_prefix(&mut self, entry: &LogEntry) -> bool {
+		if let Some(prefix) = self.parse_word("Our prefix:", &entry.message) {
+			if let Some(previous) = &self.genesis_prefix {
+				if *previous != prefix {
+					let message = format!("Prefix changed from {} to {}", previous, prefix);
+					self.genesis_prefix_changed = entry.time;
+					self.push_notification(NotificationLevel::Info, message);
+				}
+			}
+			self.parser_output = format!("PREFIX: {}", prefix);
+			self.genesis_prefix = Some(prefix);
+			return true;
+		}
+		false
+	}
+
+	///! Capture whether the node has quorum from:
+	///!	'Quorum reached with 5 votes'
+	///!	'Quorum lost'
+	fn parse_quorum_status(&mut self, entry: &LogEntry) -> bool {
+		let quorum = if let Some(votes) = self.parse_usize("Quorum reached with", &entry.message) {
+			self.quorum_votes = votes;
+			true
+		} else if entry.message.contains("Quorum lost") {
+			self.quorum_votes = 0;
+			false
+		} else {
+			return false;
+		};
+
+		if quorum != self.quorum || !self.quorum_known {
+			let message = if quorum {
+				format!("Quorum reached with {} votes", self.quorum_votes)
+			} else {
+				String::from("Quorum lost")
+			};
+			self.push_notification(NotificationLevel::Critical, message);
+		}
+		self.quorum = quorum;
+		self.quorum_known = true;
+		self.parser_output = format!("QUORUM: {} ({} votes)", quorum, self.quorum_votes);
+		true
+	}
+
+	pub fn push_notification(&mut self, level: NotificationLevel, message: String) {
+		debug_log!(format!("NOTIFICATION: {}", message).as_str());
+		self.notifications.push(Notification {
+			level,
+			time: self.most_recent,
+			raised: Utc::now(),
+			message,
+		});
+		let len = self.notifications.len();
+		if len > MAX_NOTIFICATIONS {
+			self.notifications = self.notifications.split_off(len - MAX_NOTIFICATIONS);
+		}
 	}
 
 	fn parse_usize(&mut self, prefix: &str, content: &str) -> Option<usize> {
@@ -1060,6 +4860,22 @@ impl NodeMetrics {
 		None
 	}
 
+	fn parse_u64(&mut self, prefix: &str, content: &str) -> Option<u64> {
+		if let Some(position) = content.find(prefix) {
+			let word: Vec<&str> = content[position + prefix.len()..]
+				.trim()
+				.splitn(2, |c| c == ' ' || c == ',')
+				.collect();
+			if word.len() > 0 {
+				match word[0].parse::<u64>() {
+					Ok(value) => return Some(value),
+					Err(_e) => self.parser_output = format!("failed to parse '{}' as u64 from: '{}'", word[0], &content[position + prefix.len()..]),
+				}
+			}
+		}
+		None
+	}
+
 	fn parse_word(&mut self, prefix: &str, content: &str) -> Option<String> {
 		if let Some(start) = content.find(prefix) {
 			let word: Vec<&str> = content[start + prefix.len()..]
@@ -1111,6 +4927,7 @@ pub struct ActivityEntry {
 	pub category: String, // First word, "Running", "INFO", "WARN" etc
 	pub time: Option<DateTime<Utc>>,
 	pub source: String,
+	pub peer_id: Option<String>, // Lowercase and possibly abbreviated, e.g. '8a3f2c'
 
 	pub parser_output: String,
 }
@@ -1124,12 +4941,84 @@ impl ActivityEntry {
 			category: entry.category.clone(),
 			time: entry.time,
 			source: entry.source.clone(),
+			peer_id: PEER_ID_PATTERN
+				.captures(&entry.message)
+				.map(|captures| captures["peer"].to_lowercase()),
 
 			parser_output: String::from(""),
 		}
 	}
 }
 
+///! A GraphViz DOT graph with a node for each monitor, labelled with its logfile and
+///! coloured by NodeAgebracket, and an edge to each other monitored node whose name
+///! matches the peer_id of one of its ActivityEntry
+pub fn topology_dot(monitors: &[&LogMonitor]) -> String {
+	let mut dot = String::from("digraph vdash {\n\tnode [style=filled];\n");
+	for monitor in monitors.iter() {
+		let colour = match monitor.metrics.agebracket {
+			NodeAgebracket::Elder => "gold",
+			NodeAgebracket::Adult => "palegreen",
+			NodeAgebracket::Infant => "lightblue",
+			NodeAgebracket::Unknown => "lightgrey",
+		};
+		dot.push_str(&format!(
+			"\tn{} [label=\"{}\", fillcolor={}];\n",
+			monitor.index,
+			monitor.logfile.replace('\\', "\\\\").replace('"', "\\\""),
+			colour
+		));
+	}
+
+	let mut edges = std::collections::BTreeSet::<(usize, usize)>::new();
+	for from in monitors.iter() {
+		for peer_id in from.metrics.activity_history.iter().filter_map(|activity| activity.peer_id.as_ref()) {
+			for to in monitors.iter() {
+				if to.index != from.index && to.metrics.is_named(peer_id) {
+					edges.insert((from.index, to.index));
+				}
+			}
+		}
+	}
+	for (from, to) in edges {
+		dot.push_str(&format!("\tn{} -> n{};\n", from, to));
+	}
+	dot.push_str("}\n");
+	dot
+}
+
+///! Writes each LogEntry on its own line in an OutputFormat (see App::stream_entries())
+enum EntryWriter<W: Write> {
+	Human(W),
+	Json(W),
+	Csv(csv::Writer<W>),
+}
+
+impl<W: Write> EntryWriter<W> {
+	fn new(format: OutputFormat, out: W) -> Result<EntryWriter<W>, Error> {
+		Ok(match format {
+			OutputFormat::Human => EntryWriter::Human(out),
+			OutputFormat::Json => EntryWriter::Json(out),
+			OutputFormat::Csv => {
+				let mut writer = csv::Writer::from_writer(out);
+				writer.write_record(&LogEntry::csv_header())?;
+				EntryWriter::Csv(writer)
+			}
+		})
+	}
+
+	fn write(&mut self, entry: &LogEntry) -> Result<(), Error> {
+		match self {
+			EntryWriter::Human(out) => writeln!(out, "{}", entry.logstring),
+			EntryWriter::Json(out) => writeln!(out, "{}", entry.to_json()),
+			EntryWriter::Csv(writer) => {
+				writer.write_record(&entry.to_csv_record())?;
+				writer.flush()
+			}
+		}
+	}
+}
+
 ///! Decoded logfile entries for a node log history
 pub struct LogEntry {
 	pub logstring: String,
@@ -1163,11 +5052,61 @@ impl LogEntry {
 		LogEntry::parse_logfile_line(line)
 	}
 
+	///! Column names for LogEntry::to_csv_record()
+	pub fn csv_header() -> csv::StringRecord {
+		csv::StringRecord::from(vec!["time_rfc3339", "category", "source", "message", "logstring"])
+	}
+
+	///! Fields for export to CSV, in the order given by LogEntry::csv_header()
+	pub fn to_csv_record(&self) -> csv::StringRecord {
+		let time = self.time.map_or(String::new(), |time| time.to_rfc3339());
+		csv::StringRecord::from(vec![
+			time.as_str(),
+			self.category.as_str(),
+			self.source.as_str(),
+			self.message.as_str(),
+			self.logstring.as_str(),
+		])
+	}
+
+	///! Fields for JSON output, named as in LogEntry::csv_header()
+	pub fn to_json(&self) -> serde_json::Value {
+		serde_json::json!({
+			"time_rfc3339": self.time.map(|time| time.to_rfc3339()),
+			"category": self.category,
+			"source": self.source,
+			"message": self.message,
+			"logstring": self.logstring,
+		})
+	}
+
+	///! Rank of the entry category for comparing severity (0 if unknown)
+	pub fn severity_rank(&self) -> u8 {
+		LogEntry::category_severity_rank(&self.category)
+	}
+
+	pub fn category_severity_rank(category: &str) -> u8 {
+		match category {
+			"TRACE" | "DEBUG" => 1,
+			"INFO" => 2,
+			"WARN" => 3,
+			"ERROR" => 4,
+			_ => 0,
+		}
+	}
+
 	///! Parse a line of the form:
 	///! 	[sn_node] INFO 2020-12-18T14:33:49.799447454+00:00 [src/node/mod.rs:97] Our Age: 5
 	///!	[sn_node] ERROR 2020-12-18T16:33:54.237345352+00:00 [src/utils.rs:52] Failed to load auto dump db at /home/mrh/.safe/node/baby-fleming-nodes/sn-node-genesis/transfers/f67c2e75cbce0a6097187cdf95be1c0963ad34105d643cbb00aa1f0e8b113761.db: No such file or directory (os error 2)
 	fn parse_logfile_line(line: &str) -> Option<LogEntry> {
-		if let Some(captures) = LOG_LINE_PATTERN.captures(line) {
+		with_custom_format(|custom_format| match custom_format {
+			Some(format) => LogEntry::parse_with_pattern(line, &format.line_pattern, &format.time_format),
+			None => LogEntry::parse_with_pattern(line, &LOG_LINE_PATTERN, LOG_LINE_PATTERN_TIME_FORMAT),
+		})
+	}
+
+	fn parse_with_pattern(line: &str, pattern: &Regex, time_format: &str) -> Option<LogEntry> {
+		if let Some(captures) = pattern.captures(line) {
 			let module = captures.name("module").map_or("", |m| m.as_str());
 			let category = captures.name("category").map_or("", |m| m.as_str());
 			let time_string = captures.name("time_string").map_or("", |m| m.as_str());
@@ -1177,16 +5116,19 @@ impl LogEntry {
 
 			let mut time_utc: Option<DateTime<Utc>> = None;
 
-			match DateTime::parse_from_str(time_string, "%+") {
+			match DateTime::parse_from_str(time_string, time_format) {
 				Ok(time) => {
 					time_utc = Some(time.with_timezone(&Utc));
 					time_str = format!("{}", time);
-					Some(time)
-				}
-				Err(e) => {
-					debug_log!(format!("ERROR parsing logfile time: {}", e).as_str());
-					None
 				}
+				// Custom formats may give a time without a timezone, taken to be UTC
+				Err(e) => match chrono::NaiveDateTime::parse_from_str(time_string, time_format) {
+					Ok(time) => {
+						time_utc = Some(chrono::TimeZone::from_utc_datetime(&Utc, &time));
+						time_str = format!("{}", time);
+					}
+					Err(_) => debug_log!(format!("ERROR parsing logfile time: {}", e).as_str()),
+				},
 			};
 			let parser_output = format!(
 				"m: {}, c: {}, t: {}, s: {}, m: {}",
@@ -1206,12 +5148,51 @@ impl LogEntry {
 	}
 }
 
+// When memory is low, full content is compressed (see LogMonitor::compress_content())
+static LOW_MEMORY_MB: u64 = 256;
+static LOW_MEMORY_CONTENT_RATIO: f64 = 0.5;
+static LOW_MEMORY_CHECK_SECONDS: u64 = 10;
+
+lazy_static::lazy_static! {
+	static ref LOW_MEMORY_STATE: Mutex<Option<(std::time::Instant, bool)>> = Mutex::new(None);
+}
+
+///! True when available system memory is below LOW_MEMORY_MB, checked at most
+///! every LOW_MEMORY_CHECK_SECONDS. Always false where /proc/meminfo is missing.
+fn is_memory_low() -> bool {
+	let mut state = LOW_MEMORY_STATE.lock().unwrap();
+	if let Some((checked, low)) = *state {
+		if checked.elapsed().as_secs() < LOW_MEMORY_CHECK_SECONDS {
+			return low;
+		}
+	}
+
+	let available_kb = std::fs::read_to_string("/proc/meminfo").ok().and_then(|meminfo| {
+		meminfo
+			.lines()
+			.find(|line| line.starts_with("MemAvailable:"))
+			.and_then(|line| line.split_whitespace().nth(1))
+			.and_then(|kb| kb.parse::<u64>().ok())
+	});
+	let low = match available_kb {
+		Some(kb) => kb / 1024 < LOW_MEMORY_MB,
+		None => false,
+	};
+	*state = Some((std::time::Instant::now(), low));
+	low
+}
+
+static MAX_BREADCRUMBS: usize = 10;
+
 ///! Active UI at top level
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum DashViewMain {
 	DashSummary,
 	DashNode,
 	DashDebug,
+	DashHealth,
+	DashSearch,
+	DashNetwork,
 }
 
 lazy_static::lazy_static! {
@@ -1227,8 +5208,26 @@ lazy_static::lazy_static! {
 
 pub struct DashState {
 	pub main_view: DashViewMain,
+	pub breadcrumb_trail: Vec<DashViewMain>, // Previous main views, for back()
 	pub active_timeline: usize,
 	pub dash_node_focus: String,
+	pub dash_vertical: DashVertical,
+	pub layout_preset_name: String,
+
+	pub help_overlay: bool, // Show KEY_BINDINGS over the current view
+	pub inspector_mode: bool, // Show LogEntry::decode() output below each logfile line
+	pub column_mode: bool, // Show up to MAX_COLUMNS nodes side by side
+	pub mini_map: bool, // Show a sidebar with a row summarising every node beside the node view
+	pub terminal_size: Option<Rect>, // When last drawn, for App::save_screenshot()
+
+	// The ':' command line (see App::run_command_line())
+	pub command_line: Option<String>,
+	pub command_message: Option<String>,
+
+	// For DashSearch (see App::search())
+	pub search_pattern: String,
+	pub search_results: Vec<(String, Vec<usize>)>,
+	pub search_selected: usize, // Counting results across all logfiles
 
 	// For --debug-window option
 	pub debug_window_list: StatefulList<String>,
@@ -1242,8 +5241,23 @@ impl DashState {
 
 		DashState {
 			main_view: DashViewMain::DashNode,
+			breadcrumb_trail: Vec::<DashViewMain>::new(),
 			active_timeline: 0,
 			dash_node_focus: String::new(),
+			dash_vertical: DashVertical::new(),
+			layout_preset_name: String::from(DEFAULT_LAYOUT_PRESET),
+
+			help_overlay: false,
+			inspector_mode: false,
+			column_mode: false,
+			mini_map: false,
+			terminal_size: None,
+			command_line: None,
+			command_message: None,
+
+			search_pattern: String::new(),
+			search_results: Vec::new(),
+			search_selected: 0,
 
 			debug_window: false,
 			debug_window_has_focus: false,
@@ -1252,6 +5266,39 @@ impl DashState {
 		}
 	}
 
+	///! The logfile and line index of the selected search result
+	pub fn selected_search_result(&self) -> Option<(String, usize)> {
+		let mut skip = self.search_selected;
+		for (logfile, indices) in self.search_results.iter() {
+			if skip < indices.len() {
+				return Some((logfile.clone(), indices[skip]));
+			}
+			skip -= indices.len();
+		}
+		None
+	}
+
+	///! Change the main view, remembering the current one for back()
+	pub fn push_view(&mut self, view: DashViewMain) {
+		self.breadcrumb_trail.push(self.main_view);
+		if self.breadcrumb_trail.len() > MAX_BREADCRUMBS {
+			self.breadcrumb_trail.remove(0);
+		}
+		self.main_view = view;
+	}
+
+	///! Return to the previous main view
+	///! Returns false if there is no previous view
+	pub fn back(&mut self) -> bool {
+		match self.breadcrumb_trail.pop() {
+			Some(view) => {
+				self.main_view = view;
+				true
+			}
+			None => false,
+		}
+	}
+
 	pub fn _debug_window(&mut self, text: &str) {
 		self.debug_window_list.items.push(text.to_string());
 		let len = self.debug_window_list.items.len();
@@ -1265,28 +5312,117 @@ impl DashState {
 			self.debug_window_list.state.select(Some(len - 1));
 		}
 	}
+
+	///! Apply a named layout preset (see LAYOUT_PRESETS)
+	pub fn layout_preset(&mut self, name: &str) -> Result<(), LayoutError> {
+		match LAYOUT_PRESETS.get(name) {
+			Some(preset) => {
+				let mut dash_vertical = std::mem::replace(&mut self.dash_vertical, DashVertical::new());
+				preset(self, &mut dash_vertical);
+				self.dash_vertical = dash_vertical;
+				self.layout_preset_name = name.to_string();
+				Ok(())
+			}
+			None => Err(LayoutError::UnknownPreset(name.to_string())),
+		}
+	}
+
+	///! Switch to the next layout preset in name order, wrapping at the end
+	pub fn next_layout_preset(&mut self) {
+		let mut names: Vec<&'static str> = LAYOUT_PRESETS.keys().cloned().collect();
+		names.sort();
+		let next_i = match names.iter().position(|&name| name == self.layout_preset_name) {
+			Some(i) => (i + 1) % names.len(),
+			None => 0,
+		};
+		let _ = self.layout_preset(names[next_i]);
+	}
 }
 
+///! Panel visibility and heights for the node dashboard
 pub struct DashVertical {
 	_active_view: usize,
+	pub show_node: bool,
+	pub show_timeline: bool,
+	pub node_height: u16,
+	pub timeline_height: u16,
 }
 
 impl DashVertical {
 	pub fn new() -> Self {
-		DashVertical { _active_view: 0 }
+		DashVertical {
+			_active_view: 0,
+			show_node: true,
+			show_timeline: true,
+			node_height: 12,
+			timeline_height: 18,
+		}
+	}
+}
+
+#[derive(Debug)]
+pub enum LayoutError {
+	UnknownPreset(String),
+}
+
+impl std::fmt::Display for LayoutError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			LayoutError::UnknownPreset(name) => write!(f, "unknown layout preset '{}'", name),
+		}
 	}
 }
 
+pub static DEFAULT_LAYOUT_PRESET: &str = "wide";
+
+type LayoutPreset = fn(&mut DashState, &mut DashVertical);
+
+lazy_static::lazy_static! {
+	static ref LAYOUT_PRESETS: HashMap<&'static str, LayoutPreset> = {
+		let mut presets = HashMap::<&'static str, LayoutPreset>::new();
+		presets.insert("wide", layout_wide);
+		presets.insert("compact", layout_compact);
+		presets.insert("single", layout_single);
+		presets
+	};
+}
+
+// Node status, full height timeline and logfile
+fn layout_wide(_dash_state: &mut DashState, dash_vertical: &mut DashVertical) {
+	*dash_vertical = DashVertical::new();
+}
+
+// Node status, reduced timeline to leave more room for the logfile
+fn layout_compact(_dash_state: &mut DashState, dash_vertical: &mut DashVertical) {
+	*dash_vertical = DashVertical::new();
+	dash_vertical.timeline_height = 9;
+}
+
+// Logfile only
+fn layout_single(_dash_state: &mut DashState, dash_vertical: &mut DashVertical) {
+	*dash_vertical = DashVertical::new();
+	dash_vertical.show_node = false;
+	dash_vertical.show_timeline = false;
+}
+
 pub fn set_main_view(view: DashViewMain, app: &mut App) {
 	if app.dash_state.main_view == view {
 		return;
 	}
 
 	save_focus(app);
-	app.dash_state.main_view = view;
+	app.dash_state.push_view(view);
 	restore_focus(app);
 }
 
+///! Return to the previous main view (see DashState::back())
+pub fn back_to_previous_view(app: &mut App) {
+	save_focus(app);
+	if app.dash_state.back() {
+		restore_focus(app);
+	}
+}
+
 pub fn save_focus(app: &mut App) {
 	match app.dash_state.main_view {
 		DashViewMain::DashSummary => {} // TODO
@@ -1296,6 +5432,9 @@ pub fn save_focus(app: &mut App) {
 			}
 		}
 		DashViewMain::DashDebug => {}
+		DashViewMain::DashHealth => {}
+		DashViewMain::DashSearch => {}
+		DashViewMain::DashNetwork => {}
 	}
 }
 
@@ -1310,5 +5449,812 @@ pub fn restore_focus(app: &mut App) {
 				app.set_logfile_with_focus(debug_logfile);
 			}
 		}
+		DashViewMain::DashHealth => {}
+		DashViewMain::DashSearch => {}
+		DashViewMain::DashNetwork => {}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn bucket_set_with(buckets: Vec<u64>) -> BucketSet {
+		let mut bucket_set = BucketSet::new(Duration::seconds(1), buckets.len());
+		bucket_set.buckets = buckets;
+		bucket_set
+	}
+
+	fn monitor_with_lines(lines: &[&str]) -> LogMonitor {
+		let config = Config::default();
+		let mut monitor = LogMonitor::new(&config, String::from("test.log"), 100);
+		for line in lines {
+			monitor.append_to_content(line).unwrap();
+		}
+		monitor
+	}
+
+	#[test]
+	fn bucket_set_normalizes_to_rate_once() {
+		let mut bucket_set = BucketSet::new(Duration::seconds(10), 3);
+		bucket_set.buckets = vec![5, 20, 123];
+		assert!(!bucket_set.is_rate());
+		bucket_set.normalize_to_rate();
+		assert!(bucket_set.is_rate());
+		assert_eq!(bucket_set.buckets, vec![0, 2, 12]);
+		bucket_set.normalize_to_rate();
+		assert_eq!(bucket_set.buckets, vec![0, 2, 12]);
+	}
+
+	#[tokio::test]
+	async fn watchdog_stops_when_dropped() {
+		let logfile = tempfile::NamedTempFile::new().unwrap();
+		let config = Config::new(vec![logfile.path().to_string_lossy().to_string()]);
+		let mut app = App::new_from_config(config).await.unwrap();
+		let mut watchdog_rx = app.watchdog_rx.take().unwrap();
+
+		let watchdog = app.watchdog(Duration::seconds(4));
+		assert_eq!(watchdog_rx.recv().await, Some(Duration::seconds(4)));
+
+		drop(watchdog);
+		let wait = std::time::Duration::from_secs(2);
+		assert!(tokio::time::timeout(wait, watchdog_rx.recv()).await.is_err());
+	}
+
+	#[test]
+	fn pending_writes_wait_times_out_on_slow_write() {
+		let pending_writes = PendingWrites::default();
+		let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+		pending_writes.spawn(String::from("slow state file"), move || {
+			release_rx.recv().ok();
+			Ok(())
+		});
+		assert_eq!(pending_writes.count(), 1);
+		assert!(!pending_writes.wait(std::time::Duration::from_millis(50)));
+
+		release_tx.send(()).unwrap();
+		assert!(pending_writes.wait(std::time::Duration::from_secs(5)));
+		assert_eq!(pending_writes.count(), 0);
+	}
+
+	#[test]
+	fn timeline_exports_influxdb_lines() {
+		let mut timeline = TimelineSet::new("PUTS".to_string());
+		let mut bucket_set = bucket_set_with(vec![1, 2]);
+		bucket_set.bucket_time = Some(chrono::TimeZone::timestamp_opt(&Utc, 1608302000, 0).unwrap());
+		timeline.bucket_sets.insert("1 second columns", bucket_set);
+		let mut tags = HashMap::new();
+		tags.insert("node", "genesis node");
+		let lines = timeline.to_influxdb_line_protocol("vdash", &tags);
+		assert_eq!(
+			lines,
+			"vdash,scale=1\\ second\\ columns,node=genesis\\ node puts=1i 1608301999000000000\n\
+			vdash,scale=1\\ second\\ columns,node=genesis\\ node puts=2i 1608302000000000000\n"
+		);
+	}
+
+	#[test]
+	fn log_entry_csv_record_matches_header() {
+		let line = "[sn_node] INFO 2020-12-18T14:33:49.799447454+00:00 [src/node/mod.rs:97] Our Age: 5";
+		let entry = LogEntry::decode(line).unwrap();
+		let record = entry.to_csv_record();
+		assert_eq!(record.len(), LogEntry::csv_header().len());
+		assert_eq!(&record[1], "INFO");
+		assert_eq!(&record[4], line);
+		assert!(record[0].starts_with("2020-12-18T14:33:49"));
+	}
+
+	#[test]
+	fn capacity_remaining_falls_as_buckets_fill() {
+		let mut timeline = TimelineSet::new("TEST".to_string());
+		timeline.add_bucket_set("seconds", Duration::seconds(1), 4);
+		let start = Utc::now();
+		timeline.update_current_time(Some(start));
+		let capacity = |timeline: &TimelineSet| timeline.get_bucket_set("seconds").unwrap().capacity_remaining();
+		assert_eq!(capacity(&timeline), 0.75);
+
+		timeline.update_current_time(Some(start + Duration::milliseconds(2500)));
+		assert_eq!(capacity(&timeline), 0.25);
+
+		timeline.update_current_time(Some(start + Duration::seconds(10)));
+		assert_eq!(capacity(&timeline), 0.0);
+	}
+
+	#[test]
+	fn section_p99_uses_recent_timings() {
+		let config = Config::default();
+		let mut metrics = NodeMetrics::new(&config);
+		for elapsed in 1..=200 {
+			metrics.parse_section_latencies(&format!("[timing] section=routing elapsed={}us", elapsed));
+		}
+		assert_eq!(metrics.section_p99("routing"), Some(198));
+		assert_eq!(metrics.section_p99("storage"), None);
+	}
+
+	#[test]
+	fn topology_links_monitors_by_peer_id() {
+		let config = Config::default();
+		let mut first = LogMonitor::new(&config, String::from("first.log"), 100);
+		let mut second = LogMonitor::new(&config, String::from("second.log"), 100);
+		first.index = 0;
+		second.index = 1;
+		second
+			.append_to_content("[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Our name: 8a3f2c1b..")
+			.unwrap();
+		let entry = LogEntry::decode(
+			"[sn_node] INFO 2020-12-18T14:33:01.000000000+00:00 [src/node/mod.rs:97] Response from peer 8A3F2C: GetChunk",
+		)
+		.unwrap();
+		first.metrics.activity_history.push(ActivityEntry::new(&entry, "GetChunk"));
+
+		let dot = topology_dot(&[&first, &second]);
+		assert!(dot.contains("\tn0 [label=\"first.log\", fillcolor=lightgrey];\n"));
+		assert!(dot.contains("\tn0 -> n1;\n"));
+		assert!(!dot.contains("n1 -> n0"));
+	}
+
+	#[test]
+	fn index_p99_warns_once_when_slow() {
+		let config = Config {
+			index_warn_us: 500,
+			..Config::default()
+		};
+		let mut monitor = LogMonitor::new(&config, String::from("test.log"), 100);
+		for us in &[100, 200, 900, 1000] {
+			monitor
+				.append_to_content(&format!(
+					"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Index updated: key=8a3f2c time={}µs",
+					us
+				))
+				.unwrap();
+		}
+		assert_eq!(monitor.metrics.index_p50_us(), Some(200));
+		assert_eq!(monitor.metrics.index_p99_us(), Some(1000));
+		assert_eq!(monitor.metrics.notifications.len(), 1);
+	}
+
+	#[test]
+	fn protocol_version_changes_are_recorded() {
+		let monitor = monitor_with_lines(&[
+			"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Protocol version negotiated: v4",
+			"[sn_node] INFO 2020-12-18T14:33:01.000000000+00:00 [src/node/mod.rs:97] Protocol version negotiated: v4",
+			"[sn_node] INFO 2020-12-18T14:33:02.000000000+00:00 [src/node/mod.rs:97] Protocol version negotiated: v5",
+		]);
+		assert_eq!(monitor.metrics.protocol_version, Some(5));
+		let versions: Vec<u8> = monitor.metrics.protocol_version_history.iter().map(|(version, _)| *version).collect();
+		assert_eq!(versions, vec![4, 5]);
+		assert_eq!(monitor.metrics.notifications.len(), 1);
+	}
+
+	#[test]
+	fn grep_count_counts_matching_lines() {
+		let config = Config::default();
+		let mut monitor = LogMonitor::new(&config, String::from("test.log"), 100);
+		for line in &["first error", "second", "third error"] {
+			monitor._append_to_content(line).unwrap();
+		}
+		let selected = monitor.content.state.selected();
+		assert_eq!(monitor.grep_count("error$").unwrap(), 2);
+		assert_eq!(monitor.content.state.selected(), selected);
+		assert!(monitor.grep_count("(").is_err());
+	}
+
+	#[test]
+	fn task_percentiles_use_recent_durations() {
+		let config = Config::default();
+		let mut monitor = LogMonitor::new(&config, String::from("test.log"), 100);
+		for ms in 1..=20 {
+			monitor
+				.append_to_content(&format!(
+					"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Task completed: handle_message in {}ms",
+					ms
+				))
+				.unwrap();
+		}
+		assert_eq!(monitor.metrics.task_p50("handle_message"), Some(10));
+		assert_eq!(monitor.metrics.task_p95("handle_message"), Some(19));
+		assert_eq!(monitor.metrics.task_p95("unknown"), None);
+
+		monitor.metrics.reset_history(); // Before reparse_history() adds them again
+		assert!(monitor.metrics.task_durations.is_empty());
+	}
+
+	#[test]
+	fn event_density_is_fraction_of_capacity() {
+		let idle = bucket_set_with(vec![0, 0]);
+		assert_eq!(idle.event_density(), 0.0);
+
+		// Two one second buckets give a capacity of 2s * 2 buckets = 4
+		let busy = bucket_set_with(vec![1, 2]);
+		assert_eq!(busy.event_density(), 0.75);
+
+		let saturated = bucket_set_with(vec![100, 100]);
+		assert_eq!(saturated.event_density(), 1.0);
+	}
+
+	#[test]
+	fn serialize_to_rrd_writes_one_update_per_bucket() {
+		let mut bucket_set = bucket_set_with(vec![3, 0, 7]);
+		bucket_set.bucket_time = Some(chrono::TimeZone::timestamp_opt(&Utc, 1_600_000_000, 0).unwrap());
+
+		let mut output = Vec::<u8>::new();
+		bucket_set.serialize_to_rrd(&mut output).unwrap();
+		assert_eq!(
+			String::from_utf8(output).unwrap(),
+			"DS:value:COUNTER:2:0:U\n\
+			update 1599999998:3\n\
+			update 1599999999:0\n\
+			update 1600000000:7\n"
+		);
+	}
+
+	#[test]
+	fn serialize_to_prometheus_writes_one_sample_per_bucket() {
+		let mut bucket_set = bucket_set_with(vec![3, 7]);
+		bucket_set.bucket_time = Some(chrono::TimeZone::timestamp_opt(&Utc, 1_600_000_000, 0).unwrap());
+
+		assert_eq!(
+			bucket_set.serialize_to_prometheus("vdash_puts", "logfile=\"node.log\"", "gauge"),
+			"# HELP vdash_puts vdash timeline in 1s buckets\n\
+			# TYPE vdash_puts gauge\n\
+			vdash_puts{logfile=\"node.log\"} 3 1599999999000\n\
+			vdash_puts{logfile=\"node.log\"} 7 1600000000000\n"
+		);
+	}
+
+	#[test]
+	fn compute_log_rate_counts_the_last_minute() {
+		assert_eq!(monitor_with_lines(&[]).compute_log_rate(), 0.0);
+		let monitor = monitor_with_lines(&[
+			"[sn_node] INFO 2020-12-18T14:30:00.000000000+00:00 [src/node/mod.rs:97] a",
+			"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] b",
+			"[sn_node] INFO 2020-12-18T14:33:30.000000000+00:00 [src/node/mod.rs:97] c",
+		]);
+		assert_eq!(monitor.compute_log_rate(), 2.0 / 60.0);
+	}
+
+	#[test]
+	fn quota_utilization_warns_over_quota_warn_pct() {
+		let config = Config {
+			quota_warn_pct: 50,
+			..Config::default()
+		};
+		let mut monitor = LogMonitor::new(&config, String::from("test.log"), 100);
+		assert_eq!(monitor.metrics.quota_utilization(), None);
+		for line in &[
+			"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Storage quota: 100 GB (used: 42.5 GB)",
+			"[sn_node] INFO 2020-12-18T14:33:01.000000000+00:00 [src/node/mod.rs:97] Storage quota: 100 GB (used: 60 GB)",
+			"[sn_node] INFO 2020-12-18T14:33:02.000000000+00:00 [src/node/mod.rs:97] Storage quota: 100 GB (used: 70 GB)",
+		] {
+			monitor.append_to_content(line).unwrap();
+		}
+		assert_eq!(monitor.metrics.quota_bytes, Some(100_000_000_000));
+		assert_eq!(monitor.metrics.quota_utilization(), Some(0.7));
+		assert_eq!(monitor.metrics.notifications.len(), 1);
+	}
+
+	#[test]
+	fn rate_per_second_uses_last_complete_bucket() {
+		let mut bucket_set = bucket_set_with(vec![0, 120, 30]);
+		bucket_set.bucket_duration = Duration::minutes(1);
+		assert_eq!(bucket_set.rate_per_second(), 2.0);
+		assert_eq!(bucket_set_with(vec![5]).rate_per_second(), 5.0);
+	}
+
+	#[test]
+	fn health_criterion_badness_ranks_errors() {
+		let mut monitor = monitor_with_lines(&[]);
+		assert_eq!(HealthCriterion::HighestErrorRate.badness(&monitor.metrics), None);
+		assert_eq!(HealthCriterion::MostRecentAnomaly.badness(&monitor.metrics), None);
+		assert_eq!(HealthCriterion::WorstHealthScore.badness(&monitor.metrics), Some(0.0));
+
+		monitor.metrics.activity_gets = 3;
+		monitor.metrics.activity_errors = 1;
+		monitor.metrics.push_notification(NotificationLevel::Info, String::from("info"));
+		assert_eq!(HealthCriterion::HighestErrorRate.badness(&monitor.metrics), Some(0.25));
+		assert_eq!(HealthCriterion::MostRecentAnomaly.badness(&monitor.metrics), None);
+
+		monitor.metrics.push_notification(NotificationLevel::Warning, String::from("warning"));
+		assert!(HealthCriterion::MostRecentAnomaly.badness(&monitor.metrics).is_some());
+	}
+
+	#[test]
+	fn score_history_follows_errors() {
+		let monitor = monitor_with_lines(&[
+			"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] one",
+			"[sn_node] ERROR 2020-12-18T14:33:01.000000000+00:00 [src/node/mod.rs:97] two",
+			"[sn_node] INFO 2020-12-18T14:33:02.000000000+00:00 [src/node/mod.rs:97] three",
+			"[sn_node] INFO 2020-12-18T14:33:03.000000000+00:00 [src/node/mod.rs:97] four",
+		]);
+		assert_eq!(monitor.metrics.compute_score_history(3), vec![60, 80, 87]);
+		assert_eq!(monitor.metrics.compute_score_history(10).len(), 4);
+	}
+
+	#[test]
+	fn time_gaps_are_annotated() {
+		let mut monitor = monitor_with_lines(&[
+			"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] one",
+			"[sn_node] INFO 2020-12-18T14:33:10.000000000+00:00 [src/node/mod.rs:97] two",
+			"not a log entry",
+			"[sn_node] INFO 2020-12-18T14:35:15.000000000+00:00 [src/node/mod.rs:97] three",
+		]);
+		assert_eq!(monitor.annotate_time_gaps(60), 1);
+		assert_eq!(monitor.content.items.len(), 5);
+		assert_eq!(monitor.content.items[3], "--- gap: 2m 5s ---");
+	}
+
+	#[test]
+	fn latest_error_is_most_recent() {
+		assert_eq!(monitor_with_lines(&[]).latest_error(), None);
+		let monitor = monitor_with_lines(&[
+			"[sn_node] ERROR 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] first failure",
+			"[sn_node] ERROR 2020-12-18T14:33:01.000000000+00:00 [src/node/mod.rs:97] second failure",
+			"[sn_node] INFO 2020-12-18T14:33:02.000000000+00:00 [src/node/mod.rs:97] recovered",
+		]);
+		assert_eq!(monitor.latest_error(), Some("second failure"));
+	}
+
+	#[test]
+	fn data_proof_rejections_warn_once() {
+		let config = Config::default();
+		let mut monitor = LogMonitor::new(&config, String::from("test.log"), 100);
+		for _ in 0..99 {
+			monitor.append_to_content("[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Data proof validated").unwrap();
+		}
+		assert_eq!(monitor.metrics.proof_acceptance_rate(), Some(1.0));
+		for _ in 0..2 {
+			monitor.append_to_content("[sn_node] WARN 2020-12-18T14:33:01.000000000+00:00 [src/node/mod.rs:97] Data proof rejected").unwrap();
+		}
+		assert_eq!(monitor.metrics.proofs_validated, 99);
+		assert_eq!(monitor.metrics.proofs_rejected, 2);
+		assert_eq!(monitor.metrics.notifications.len(), 1);
+	}
+
+	#[test]
+	fn merge_from_includes_alerts() {
+		let config = Config::default();
+		let mut merged = NodeMetrics::new(&config);
+		let monitor = monitor_with_lines(&[
+			"[sn_node] ERROR 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Checksum failed for chunk 8a3f2c",
+			"[sn_node] WARN 2020-12-18T14:33:01.000000000+00:00 [src/node/mod.rs:97] Peer 1b2c3d running version v0.23.0 (expected v0.24.0)",
+		]);
+		merged.merge_from(&monitor.metrics);
+		merged.merge_from(&monitor.metrics);
+		assert_eq!(merged.corruption_events, 2);
+		assert_eq!(merged.corruptions[0].chunk_id.as_deref(), Some("8a3f2c"));
+		assert_eq!(merged.version_mismatches, 2);
+		assert_eq!(merged.mismatched_versions, vec!["v0.23.0"]);
+	}
+
+	#[test]
+	fn active_bans_expire() {
+		let mut monitor = monitor_with_lines(&[
+			"[sn_node] WARN 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Peer 8a3f2c banned for 60 seconds",
+			"[sn_node] WARN 2020-12-18T14:33:30.000000000+00:00 [src/node/mod.rs:97] Peer 1b2c3d banned for 600 seconds",
+		]);
+		assert_eq!(monitor.metrics.banned_peers.len(), 2);
+		assert_eq!(monitor.metrics.active_bans(), 2);
+		assert_eq!(monitor.metrics.notifications.len(), 2);
+
+		monitor.append_to_content("[sn_node] INFO 2020-12-18T14:35:00.000000000+00:00 [src/node/mod.rs:97] Chunk stored").unwrap();
+		assert_eq!(monitor.metrics.active_bans(), 1);
+	}
+
+	#[test]
+	fn lock_held_warns_once_over_threshold() {
+		let config = Config {
+			lock_warn_ms: 100,
+			..Config::default()
+		};
+		let mut monitor = LogMonitor::new(&config, String::from("test.log"), 100);
+		for line in &[
+			"[sn_node] WARN 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Lock contention detected for mutex: chunk_store",
+			"[sn_node] INFO 2020-12-18T14:33:01.000000000+00:00 [src/node/mod.rs:97] Mutex held for 50ms",
+			"[sn_node] INFO 2020-12-18T14:33:02.000000000+00:00 [src/node/mod.rs:97] Mutex held for 150ms",
+			"[sn_node] INFO 2020-12-18T14:33:03.000000000+00:00 [src/node/mod.rs:97] Mutex held for 250ms",
+		] {
+			monitor.append_to_content(line).unwrap();
+		}
+		assert_eq!(monitor.metrics.lock_contention_events, 1);
+		assert_eq!(monitor.metrics.max_lock_held_ms, 250);
+		assert_eq!(monitor.metrics.notifications.len(), 1);
+	}
+
+	#[test]
+	fn pow_averages_reduce_health_when_slow() {
+		let monitor = monitor_with_lines(&[
+			"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] PoW completed in 4000ms with difficulty 16",
+			"[sn_node] INFO 2020-12-18T14:33:01.000000000+00:00 [src/node/mod.rs:97] PoW completed in 8000ms with difficulty 20",
+		]);
+		assert_eq!(monitor.metrics.pow_completions, 2);
+		assert_eq!(monitor.metrics.pow_avg_ms, 6000.0);
+		assert_eq!(monitor.metrics.pow_avg_difficulty, 18.0);
+		assert_eq!(monitor.metrics.health_score(), 90);
+	}
+
+	#[test]
+	fn chunk_validation_rate_warns_once_when_low() {
+		assert_eq!(monitor_with_lines(&[]).metrics.chunk_validation_rate(), None);
+		let monitor = monitor_with_lines(&[
+			"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Chunk validation passed",
+			"[sn_node] INFO 2020-12-18T14:33:01.000000000+00:00 [src/node/mod.rs:97] Chunk validation failed",
+			"[sn_node] INFO 2020-12-18T14:33:02.000000000+00:00 [src/node/mod.rs:97] Chunk validation failed",
+			"[sn_node] INFO 2020-12-18T14:33:03.000000000+00:00 [src/node/mod.rs:97] Chunk validation passed",
+		]);
+		assert_eq!(monitor.metrics.chunk_validation_rate(), Some(0.5));
+		assert_eq!(monitor.metrics.notifications.len(), 1);
+	}
+
+	#[test]
+	fn parse_lines_update_metrics() {
+		let cases: &[(&str, fn(&NodeMetrics) -> u64, u64)] = &[
+			(
+				"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Synchronising data with peer 8a3f2c",
+				|metrics| metrics.sync_events.len() as u64,
+				1,
+			),
+			(
+				"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Penalty applied: 10 points",
+				|metrics| metrics.total_penalties,
+				10,
+			),
+			(
+				"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Beacon response from 8a3f2c.. in 100ms",
+				|metrics| metrics.beacon_responses.iter().sum::<u64>(),
+				100,
+			),
+			(
+				"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Beacon response from 8a3f2c.. in soon",
+				|metrics| metrics.beacon_responses.len() as u64,
+				0,
+			),
+			(
+				"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Token transfer: 25 tokens from 8a3f2c to 1b2c3d",
+				|metrics| metrics.total_transferred,
+				25,
+			),
+			(
+				"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Gossip message sent to 5 peers",
+				|metrics| metrics.gossip_sent,
+				1,
+			),
+			(
+				"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Gossip received from peer 8a3f2c",
+				|metrics| metrics.gossip_received,
+				1,
+			),
+			(
+				"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Currently 12 shards in network",
+				|metrics| metrics.shard_count.unwrap_or(0) as u64,
+				12,
+			),
+			(
+				"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Added 3 to routing table",
+				|metrics| metrics.routing_additions,
+				3,
+			),
+			(
+				"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Removed 1 from routing table",
+				|metrics| metrics.routing_removals,
+				1,
+			),
+			(
+				"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Section churn: 4 joins, 3 leaves in last epoch",
+				|metrics| metrics.section_joins,
+				4,
+			),
+			(
+				"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Section churn: 4 joins, 3 leaves in last epoch",
+				|metrics| metrics.section_leaves,
+				3,
+			),
+			(
+				"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Cache hit: key=8a3f2c",
+				|metrics| metrics.cache_hits,
+				1,
+			),
+			(
+				"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Cache miss: key=1b2c3d",
+				|metrics| metrics.cache_misses,
+				1,
+			),
+			(
+				"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Signature verified in 80µs",
+				|metrics| metrics.sig_avg_us as u64,
+				80,
+			),
+			(
+				"[sn_node] WARN 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Signature verification failed",
+				|metrics| metrics.sig_failures,
+				1,
+			),
+			(
+				"[sn_node] WARN 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Request timed out after 5000ms",
+				|metrics| metrics.timeouts,
+				1,
+			),
+			(
+				"[sn_node] WARN 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Operation timeout: GetChunk",
+				|metrics| metrics.timeouts,
+				1,
+			),
+		];
+		for (line, value, expected) in cases {
+			let monitor = monitor_with_lines(&[line]);
+			assert_eq!(value(&monitor.metrics), *expected, "{}", line);
+		}
+	}
+
+	#[test]
+	fn entry_writer_writes_each_format() {
+		let line = "[sn_node] INFO 2020-12-18T14:33:49.799447454+00:00 [src/node/mod.rs:97] Our Age: 5";
+		let entry = LogEntry::decode(line).unwrap();
+		let written = |format| {
+			let mut writer = EntryWriter::new(format, Vec::<u8>::new()).unwrap();
+			writer.write(&entry).unwrap();
+			match writer {
+				EntryWriter::Human(out) | EntryWriter::Json(out) => String::from_utf8(out).unwrap(),
+				EntryWriter::Csv(writer) => String::from_utf8(writer.into_inner().unwrap()).unwrap(),
+			}
+		};
+
+		assert_eq!(written(OutputFormat::Human), format!("{}\n", line));
+		let json: serde_json::Value = serde_json::from_str(&written(OutputFormat::Json)).unwrap();
+		assert_eq!(json["message"], "Our Age: 5");
+		assert_eq!(written(OutputFormat::Csv).lines().count(), 2);
+	}
+
+	#[test]
+	fn apply_highlight_rules_emboldens_own_id() {
+		let config = Config {
+			highlight_own_id: Some(String::from("8A3F2C1B9D0E4F56")),
+			..Config::default()
+		};
+		let monitor = LogMonitor::new(&config, String::from("test.log"), 100);
+		let mine = "[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Our name: 8a3f2c1b..";
+		let theirs = "[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Peer 1b2c3d4e..";
+		assert!(monitor.apply_highlight_rules(mine).add_modifier.contains(Modifier::BOLD));
+		assert!(!monitor.apply_highlight_rules(theirs).add_modifier.contains(Modifier::BOLD));
+	}
+
+	#[test]
+	fn clone_config_into_keeps_recent_content() {
+		let config = Config {
+			deduplicate: true,
+			min_severity: Some(LogEntry::category_severity_rank("WARN")),
+			..Config::default()
+		};
+		let mut source = LogMonitor::new(&config, String::from("source.log"), 1);
+		source.has_focus = true;
+		let mut target = LogMonitor::new(&Config::default(), String::from("target.log"), 100);
+		target._append_to_content("trimmed").unwrap();
+		target._append_to_content("kept").unwrap();
+
+		source.clone_config_into(&mut target);
+		assert_eq!(target.max_content, 1);
+		assert_eq!(target.min_severity, source.min_severity);
+		assert_eq!(target.deduplicate_window, source.deduplicate_window);
+		assert!(!target.has_focus);
+		assert_eq!(target.logfile, "target.log");
+		assert_eq!(target.content.items, vec!["kept"]);
+	}
+
+	#[test]
+	fn transform_line_applies_in_order() {
+		let transforms: Vec<LineTransform> = vec![
+			r"8a3f2c:1b2c3d".parse().unwrap(),
+			r"peer [0-9a-f]+:peer <redacted>".parse().unwrap(),
+		];
+		assert_eq!(transform_line("peer 8a3f2c joined", &transforms), "peer <redacted> joined");
+		assert_eq!(transform_line("unchanged", &transforms), "unchanged");
+	}
+
+	#[test]
+	fn apply_transforms_replaces_in_place() {
+		let config = Config {
+			transforms: vec!["peer [0-9a-f]+:peer <redacted>".parse().unwrap()],
+			..Config::default()
+		};
+		let mut monitor = LogMonitor::new(&config, String::from("test.log"), 100);
+		monitor
+			.append_to_content("[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] peer 8a3f2c")
+			.unwrap();
+		assert_eq!(
+			monitor.content.items[0],
+			"[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] peer <redacted>"
+		);
+		let transforms: Vec<LineTransform> = vec![r"^\[sn_node\] :".parse().unwrap()];
+		assert!("no separator".parse::<LineTransform>().is_err());
+
+		monitor.apply_transforms(transforms).unwrap();
+		assert_eq!(
+			monitor.content.items[0],
+			"INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] peer <redacted>"
+		);
+	}
+
+	#[test]
+	fn connection_failures_reduce_health_score() {
+		let config = Config {
+			conn_failure_threshold: 2,
+			..Config::default()
+		};
+		let mut monitor = LogMonitor::new(&config, String::from("test.log"), 100);
+		assert_eq!(monitor.metrics.health_score(), 100);
+		for line in &[
+			"[sn_node] WARN 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] Connection refused by peer 8a3f2c",
+			"[sn_node] WARN 2020-12-18T14:33:01.000000000+00:00 [src/node/mod.rs:97] Failed to connect to bootstrap node 1b2c3d",
+		] {
+			monitor.append_to_content(line).unwrap();
+		}
+		assert_eq!(monitor.metrics.connection_failures, 2);
+		assert!(!monitor.metrics.connection_failures_high());
+		assert_eq!(monitor.metrics.health_score(), 80);
+	}
+
+	#[test]
+	fn check_silence_notifies_stale_monitor_once() {
+		let mut monitor =
+			monitor_with_lines(&["[sn_node] INFO 2020-12-18T14:33:00.000000000+00:00 [src/node/mod.rs:97] a"]);
+		let most_recent = monitor.metrics.most_recent.unwrap();
+		let timeout = Duration::seconds(60);
+
+		monitor.metrics.check_silence(most_recent + Duration::seconds(30), timeout);
+		assert!(monitor.metrics.notifications.is_empty());
+
+		monitor.metrics.check_silence(most_recent + Duration::seconds(90), timeout);
+		monitor.metrics.check_silence(most_recent + Duration::seconds(120), timeout);
+		assert_eq!(monitor.metrics.notifications.len(), 1);
+		assert!(monitor.metrics.notifications[0].level == NotificationLevel::Critical);
+	}
+
+	#[test]
+	fn sum_across_sets_pads_shorter_sets() {
+		let mut timeline = TimelineSet::new("TEST".to_string());
+		assert!(timeline.sum_across_sets().is_err());
+
+		timeline.add_bucket_set("seconds", Duration::seconds(1), 3);
+		timeline.add_bucket_set("minutes", Duration::minutes(1), 2);
+		timeline.bucket_sets.get_mut("seconds").unwrap().buckets = vec![1, 2, 3];
+		timeline.bucket_sets.get_mut("minutes").unwrap().buckets = vec![10, 20];
+
+		let sum = timeline.sum_across_sets().unwrap();
+		assert_eq!(sum.buckets, vec![1, 12, 23]);
+		assert_eq!(sum.bucket_duration, Duration::seconds(1));
+	}
+
+	#[test]
+	fn align_with_pads_the_shorter_bucket_set() {
+		let short = bucket_set_with(vec![3, 4]);
+		let long = bucket_set_with(vec![1, 2, 3, 4]);
+		assert_eq!(short.align_with(&long).unwrap(), (vec![0, 0, 3, 4], vec![1, 2, 3, 4]));
+		assert_eq!(long.align_with(&short).unwrap(), (vec![1, 2, 3, 4], vec![0, 0, 3, 4]));
+
+		let mut minutes = bucket_set_with(vec![1, 2]);
+		minutes.bucket_duration = Duration::minutes(1);
+		assert!(short.align_with(&minutes).is_err());
+
+		let mut merged = bucket_set_with(vec![1, 1]);
+		merged.merge_from(&long);
+		assert_eq!(merged.buckets, vec![4, 5]);
+	}
+
+	#[test]
+	fn lag_finds_offset_of_trailing_activity() {
+		let leader = bucket_set_with(vec![0, 5, 1, 0, 0, 0, 0, 0]);
+		let follower = bucket_set_with(vec![0, 0, 0, 5, 1, 0, 0, 0]);
+		assert_eq!(leader.lag(&follower), Some(Duration::seconds(2)));
+		assert_eq!(follower.lag(&leader), Some(Duration::seconds(-2)));
+		assert_eq!(leader.lag(&leader), Some(Duration::zero()));
+		assert_eq!(leader.lag(&bucket_set_with(vec![1, 2, 3])), None);
+	}
+
+	#[test]
+	fn detect_plateau_finds_most_recent_level_run() {
+		let bucket_set = bucket_set_with(vec![1, 100, 101, 100, 99, 7, 50, 50, 50, 3]);
+		assert_eq!(bucket_set.detect_plateau(3, 5.0), Some(6));
+		assert_eq!(bucket_set.detect_plateau(4, 5.0), Some(1));
+		assert_eq!(bucket_set.detect_plateau(5, 5.0), None);
+		assert_eq!(bucket_set.detect_plateau(11, 5.0), None);
+	}
+
+	#[test]
+	fn fill_gaps_only_fills_long_runs_of_zeroes() {
+		let buckets = vec![0, 0, 0, 0, 4, 0, 0, 0, 0, 8, 0, 0, 1];
+
+		let mut zero = bucket_set_with(buckets.clone());
+		zero.fill_gaps(GapFillStrategy::Zero);
+		assert_eq!(zero.buckets, buckets);
+
+		let mut hold = bucket_set_with(buckets.clone());
+		hold.fill_gaps(GapFillStrategy::Hold);
+		assert_eq!(hold.buckets, vec![0, 0, 0, 0, 4, 4, 4, 4, 4, 8, 0, 0, 1]);
+
+		let mut interpolate = bucket_set_with(buckets);
+		interpolate.fill_gaps(GapFillStrategy::Interpolate);
+		assert_eq!(interpolate.buckets, vec![0, 0, 0, 0, 4, 5, 6, 6, 7, 8, 0, 0, 1]);
+	}
+
+	#[test]
+	fn truncate_before_removes_older_entries_and_lines() {
+		let mut monitor = monitor_with_lines(&[
+			"[sn_node] INFO 2020-12-18T14:30:00.000000000+00:00 [src/node/mod.rs:97] a",
+			"[sn_node] INFO 2020-12-18T14:31:00.000000000+00:00 [src/node/mod.rs:97] b",
+			"[sn_node] INFO 2020-12-18T14:32:00.000000000+00:00 [src/node/mod.rs:97] c",
+		]);
+
+		let cutoff = monitor.metrics.most_recent.unwrap() - Duration::seconds(90);
+		assert_eq!(monitor.truncate_before(cutoff), 2);
+		assert_eq!(monitor.metrics.log_history.len(), 2);
+		assert_eq!(monitor.content.items.len(), 2);
+		assert!(monitor.content.items[0].ends_with(" b"));
+	}
+
+	#[test]
+	fn selected_search_result_counts_across_logfiles() {
+		let mut dash_state = DashState::new();
+		dash_state.search_results = vec![
+			(String::from("a.log"), vec![3, 7]),
+			(String::from("b.log"), vec![1]),
+		];
+		dash_state.search_selected = 2;
+		assert_eq!(dash_state.selected_search_result(), Some((String::from("b.log"), 1)));
+		dash_state.search_selected = 3;
+		assert_eq!(dash_state.selected_search_result(), None);
+	}
+
+	#[test]
+	fn trend_direction_compares_halves() {
+		assert_eq!(bucket_set_with(vec![1, 2, 3]).trend_direction(), TrendDirection::Insufficient);
+		assert_eq!(bucket_set_with(vec![1, 1, 5, 5]).trend_direction(), TrendDirection::Rising);
+		assert_eq!(bucket_set_with(vec![5, 5, 1, 1]).trend_direction(), TrendDirection::Falling);
+		assert_eq!(bucket_set_with(vec![10, 10, 9, 10, 10]).trend_direction(), TrendDirection::Stable);
+		assert_eq!(bucket_set_with(vec![0, 0, 0, 0]).trend_direction(), TrendDirection::Stable);
+	}
+
+	#[test]
+	fn compress_content_removes_debug_lines_first() {
+		let mut monitor = monitor_with_lines(&[
+			"[sn_node] INFO a",
+			"[sn_node] DEBUG b",
+			"[sn_node] INFO c",
+			"[sn_node] TRACE d",
+			"[sn_node] WARN e",
+			"[sn_node] INFO f",
+		]);
+		assert_eq!(monitor.compress_content(0.5), 3);
+		assert_eq!(
+			monitor.content.items,
+			vec!["[sn_node] INFO c", "[sn_node] WARN e", "[sn_node] INFO f"]
+		);
+	}
+
+	#[test]
+	fn annotate_labels_only_given_buckets() {
+		let bucket_set = bucket_set_with(vec![0, 0, 0, 0]);
+		let labels = bucket_set.annotate(&[(1, "restart".to_string()), (9, "ignored".to_string())]);
+		assert_eq!(labels, vec![None, Some("restart".to_string()), None, None]);
+	}
+
+	#[test]
+	fn predict_next_last_value() {
+		let bucket_set = bucket_set_with(vec![3, 1, 4, 1, 5]);
+		assert_eq!(bucket_set.predict_next(PredictionMethod::LastValue), 5);
+	}
+
+	#[test]
+	fn predict_next_moving_average() {
+		let bucket_set = bucket_set_with(vec![100, 2, 4, 6]);
+		assert_eq!(bucket_set.predict_next(PredictionMethod::MovingAverage(3)), 4);
+		assert_eq!(bucket_set.predict_next(PredictionMethod::MovingAverage(10)), 28);
+	}
+
+	#[test]
+	fn predict_next_linear_regression() {
+		let rising = bucket_set_with(vec![1, 2, 3, 4]);
+		assert_eq!(rising.predict_next(PredictionMethod::LinearRegression), 5);
+
+		let falling = bucket_set_with(vec![9, 6, 3, 0]);
+		assert_eq!(falling.predict_next(PredictionMethod::LinearRegression), 0);
 	}
 }
@@ -0,0 +1,180 @@
+///! Settings used to create an App
+///!
+///! Config is independent of the command line so that an App can be created
+///! by tests or when using vdash as a library. Use Config::from_opt() for
+///! settings from the command line. Fields and their defaults match those
+///! of Opt, which documents each of them.
+
+use std::io::{Error, ErrorKind};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use chrono::Duration;
+use regex::Regex;
+
+use crate::custom::app::LineTransform;
+use crate::custom::format_loader::LogFormat;
+use crate::custom::opt::{Opt, OutputFormat, MIN_TIMELINE_STEPS};
+
+static DEFAULT_SECTION_PATTERN: &str = r"={3,}\s*(.+?)\s*={3,}";
+
+#[derive(Debug)]
+pub struct Config {
+	pub files: Vec<String>,
+	pub lines_max: usize,
+	pub max_monitors: usize,
+	pub retention: Option<i64>,
+	pub watchdog_timeout: i64,
+	pub graceful_shutdown_timeout: u64,
+	pub tick_rate: u64,
+	pub timeline_steps: usize,
+	pub ignore_existing: bool,
+	pub transforms: Vec<LineTransform>,
+	pub debug_window: bool,
+	pub debug_dashboard: bool,
+	pub layout_preset: String,
+	pub log_format: LogFormat,
+	pub min_severity: Option<u8>,
+	pub deduplicate: bool,
+	pub deduplicate_window: usize,
+	pub disk_alert_gb: Option<f64>,
+	pub memory_warn_mb: u64,
+	pub conn_failure_threshold: u64,
+	pub quota_warn_pct: u64,
+	pub index_warn_us: u64,
+	pub lock_warn_ms: u64,
+	pub min_replication: u8,
+	pub section_pattern: Regex,
+	pub highlight_own_id: Option<String>,
+	pub tags: Vec<String>,
+	pub time_offset: Option<Duration>,
+	pub format: OutputFormat,
+	pub stress_test: Option<u64>,
+	pub stress_duration: i64,
+	pub ansi_log: bool,
+	pub force_tui: bool,
+	pub clipboard: bool,
+	pub remote: Option<SocketAddr>,
+	pub emit_metrics_port: Option<u16>,
+	pub graphviz_path: PathBuf,
+	pub signal_export_path: PathBuf,
+	pub tee: Option<PathBuf>,
+	#[cfg(feature = "web-ui")]
+	pub web_ui_port: Option<u16>,
+}
+
+impl Default for Config {
+	fn default() -> Config {
+		Config {
+			files: Vec::new(),
+			lines_max: 100,
+			max_monitors: 20,
+			retention: None,
+			watchdog_timeout: 60,
+			graceful_shutdown_timeout: 5,
+			tick_rate: 200,
+			timeline_steps: 210,
+			ignore_existing: false,
+			transforms: Vec::new(),
+			debug_window: false,
+			debug_dashboard: false,
+			layout_preset: String::from("wide"),
+			log_format: LogFormat::SafeVault,
+			min_severity: None,
+			deduplicate: false,
+			deduplicate_window: 10,
+			disk_alert_gb: None,
+			memory_warn_mb: 512,
+			conn_failure_threshold: 10,
+			quota_warn_pct: 80,
+			index_warn_us: 10000,
+			lock_warn_ms: 100,
+			min_replication: 3,
+			section_pattern: Regex::new(DEFAULT_SECTION_PATTERN).unwrap(),
+			highlight_own_id: None,
+			tags: Vec::new(),
+			time_offset: None,
+			format: OutputFormat::Human,
+			stress_test: None,
+			stress_duration: 10,
+			ansi_log: false,
+			force_tui: false,
+			clipboard: false,
+			remote: None,
+			emit_metrics_port: None,
+			graphviz_path: PathBuf::from("vdash-topology.dot"),
+			signal_export_path: PathBuf::from("/tmp/vdash-metrics.json"),
+			tee: None,
+			#[cfg(feature = "web-ui")]
+			web_ui_port: None,
+		}
+	}
+}
+
+impl Config {
+	///! Default settings for monitoring the given logfiles
+	pub fn new(files: Vec<String>) -> Config {
+		Config {
+			files,
+			..Config::default()
+		}
+	}
+
+	///! Settings from the command line, or an InvalidInput error describing what is wrong
+	pub fn from_opt(opt: Opt) -> Result<Config, Error> {
+		if opt.files.is_empty() && opt.remote.is_none() && opt.stress_test.is_none() {
+			return Err(Error::new(ErrorKind::InvalidInput, "no logfile(s) specified."));
+		}
+
+		if opt.timeline_steps < MIN_TIMELINE_STEPS {
+			return Err(Error::new(
+				ErrorKind::InvalidInput,
+				format!("Timeline steps number is too small, minimum is {}", MIN_TIMELINE_STEPS),
+			));
+		}
+
+		Ok(Config {
+			files: opt.files,
+			lines_max: opt.lines_max,
+			max_monitors: opt.max_monitors,
+			retention: opt.retention,
+			watchdog_timeout: opt.watchdog_timeout,
+			graceful_shutdown_timeout: opt.graceful_shutdown_timeout,
+			tick_rate: opt.tick_rate,
+			timeline_steps: opt.timeline_steps,
+			ignore_existing: opt.ignore_existing,
+			transforms: opt.transforms,
+			debug_window: opt.debug_window,
+			debug_dashboard: opt.debug_dashboard,
+			layout_preset: opt.layout_preset,
+			log_format: opt.log_format,
+			min_severity: opt.min_severity,
+			deduplicate: opt.deduplicate,
+			deduplicate_window: opt.deduplicate_window,
+			disk_alert_gb: opt.disk_alert_gb,
+			memory_warn_mb: opt.memory_warn_mb,
+			conn_failure_threshold: opt.conn_failure_threshold,
+			quota_warn_pct: opt.quota_warn_pct,
+			index_warn_us: opt.index_warn_us,
+			lock_warn_ms: opt.lock_warn_ms,
+			min_replication: opt.min_replication,
+			section_pattern: opt.section_pattern,
+			highlight_own_id: opt.highlight_own_id,
+			tags: opt.tags,
+			time_offset: opt.time_offset,
+			format: opt.format,
+			stress_test: opt.stress_test,
+			stress_duration: opt.stress_duration,
+			ansi_log: opt.ansi_log,
+			force_tui: opt.force_tui,
+			clipboard: opt.clipboard,
+			remote: opt.remote,
+			emit_metrics_port: opt.emit_metrics_port,
+			graphviz_path: opt.graphviz_path,
+			signal_export_path: opt.signal_export_path,
+			tee: opt.tee,
+			#[cfg(feature = "web-ui")]
+			web_ui_port: opt.web_ui_port,
+		})
+	}
+}
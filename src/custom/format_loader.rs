@@ -0,0 +1,154 @@
+///! Custom logfile formats (see --log-format)
+///!
+///! A custom format is a TOML file giving a regex for each field of a
+///! logfile line, for example:
+///!
+///!	category = "[A-Z]+"
+///!	time = "\\S+"
+///!	source = "\\[[^\\]]*\\]"
+///!	message = ".*"
+///!
+///! Optional settings are the order of the fields in a line (default
+///! ["category", "time", "source", "message"]), the regex separating them
+///! (default "\\s+") and a chrono format for the time (default "%+").
+
+use regex::Regex;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use crate::custom::app::LOG_LINE_PATTERN_TIME_FORMAT;
+
+///! Value of --log-format
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogFormat {
+	SafeVault,
+	Custom(PathBuf),
+}
+
+impl std::str::FromStr for LogFormat {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<LogFormat, String> {
+		if s == "safe-vault" {
+			return Ok(LogFormat::SafeVault);
+		}
+		match s.strip_prefix("custom:") {
+			Some(path) if !path.is_empty() => Ok(LogFormat::Custom(PathBuf::from(path))),
+			_ => Err(format!("unknown log format '{}', use 'safe-vault' or 'custom:<file.toml>'", s)),
+		}
+	}
+}
+
+#[derive(Deserialize)]
+struct FormatSpec {
+	category: String,
+	time: String,
+	source: String,
+	message: String,
+	order: Option<Vec<String>>,
+	separator: Option<String>,
+	time_format: Option<String>,
+}
+
+///! A compiled custom format, used by LogEntry::decode() in place of the SAFE format
+pub struct CustomFormat {
+	pub line_pattern: Regex,
+	pub time_format: String,
+}
+
+lazy_static::lazy_static! {
+	static ref CUSTOM_FORMAT: RwLock<Option<CustomFormat>> = RwLock::new(None);
+}
+
+///! Read and compile a custom format from a TOML file
+pub fn load_format(path: &Path) -> Result<CustomFormat, String> {
+	let toml_string = std::fs::read_to_string(path)
+		.map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+	let spec: FormatSpec = toml::from_str(&toml_string)
+		.map_err(|e| format!("failed to parse {}: {}", path.display(), e))?;
+
+	let order = spec.order.clone().unwrap_or_else(|| {
+		vec!["category", "time", "source", "message"]
+			.iter()
+			.map(|field| field.to_string())
+			.collect()
+	});
+	let separator = spec.separator.clone().unwrap_or_else(|| String::from(r"\s+"));
+	let mut fields = Vec::<String>::new();
+	for field in order.iter() {
+		let (name, pattern) = match field.as_str() {
+			"category" => ("category", &spec.category),
+			"time" => ("time_string", &spec.time),
+			"source" => ("source", &spec.source),
+			"message" => ("message", &spec.message),
+			_ => return Err(format!("unknown field '{}' in order of {}", field, path.display())),
+		};
+		fields.push(format!("(?P<{}>{})", name, pattern));
+	}
+
+	let line_pattern = Regex::new(&format!("^{}", fields.join(&separator)))
+		.map_err(|e| format!("invalid pattern in {}: {}", path.display(), e))?;
+	Ok(CustomFormat {
+		line_pattern,
+		time_format: spec
+			.time_format
+			.unwrap_or_else(|| LOG_LINE_PATTERN_TIME_FORMAT.to_string()),
+	})
+}
+
+///! Use format for all logfiles in place of the SAFE format
+pub fn set_custom_format(format: CustomFormat) {
+	*CUSTOM_FORMAT.write().unwrap() = Some(format);
+}
+
+///! Call f with the custom format, or None if the SAFE format is in use
+pub fn with_custom_format<T>(f: impl FnOnce(Option<&CustomFormat>) -> T) -> T {
+	f(CUSTOM_FORMAT.read().unwrap().as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Write;
+
+	#[test]
+	fn load_format_builds_line_pattern() {
+		let mut file = tempfile::NamedTempFile::new().unwrap();
+		writeln!(
+			file,
+			"{}",
+			r#"
+order = ["time", "category", "message", "source"]
+separator = " \\| "
+category = "[A-Z]+"
+time = "\\S+"
+source = "\\S+"
+message = "[^|]*?"
+time_format = "%Y-%m-%dT%H:%M:%S"
+"#
+		)
+		.unwrap();
+
+		let format = load_format(file.path()).unwrap();
+		let captures = format
+			.line_pattern
+			.captures("2021-01-02T03:04:05 | WARN | disk is slow | store.rs")
+			.unwrap();
+		assert_eq!(&captures["category"], "WARN");
+		assert_eq!(&captures["time_string"], "2021-01-02T03:04:05");
+		assert_eq!(&captures["message"], "disk is slow");
+		assert_eq!(&captures["source"], "store.rs");
+		assert_eq!(format.time_format, "%Y-%m-%dT%H:%M:%S");
+	}
+
+	#[test]
+	fn log_format_from_str() {
+		assert_eq!("safe-vault".parse::<LogFormat>(), Ok(LogFormat::SafeVault));
+		assert_eq!(
+			"custom:my.toml".parse::<LogFormat>(),
+			Ok(LogFormat::Custom(PathBuf::from("my.toml")))
+		);
+		assert!("syslog".parse::<LogFormat>().is_err());
+	}
+}
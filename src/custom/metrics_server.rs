@@ -0,0 +1,187 @@
+///! Prometheus compatible /metrics endpoint (see --emit-metrics-port)
+///!
+///! NodeMetrics are copied into a prometheus::Registry on each tick
+///! by App::export_metrics(), and served by a separate tokio task.
+///!
+///! Each scalar count is exported as a counter (named '..._total') and each
+///! scalar reading as a gauge. Breakdowns such as gets_by_type, and the
+///! timelines, are left out. A counter is reset if its count goes down,
+///! such as when a logfile is re-read.
+
+use prometheus::{Encoder, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder};
+use std::io::{Error, ErrorKind};
+use std::net::Ipv4Addr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::custom::app::LogMonitor;
+
+static METRICS_PREFIX: &str = "vdash";
+static LOGFILE_LABEL: &str = "logfile";
+static TAGS_LABEL: &str = "tags"; // Comma separated session tags (see --tag)
+
+type CounterSpec = (&'static str, &'static str, fn(&LogMonitor) -> u64);
+type GaugeSpec = (&'static str, &'static str, fn(&LogMonitor) -> Option<i64>);
+
+static COUNTERS: &[CounterSpec] = &[
+	("gets_total", "GET operations", |m| m.metrics.activity_gets),
+	("puts_total", "PUT operations", |m| m.metrics.activity_puts),
+	("errors_total", "ERROR logfile entries", |m| m.metrics.activity_errors),
+	("elections_total", "Elder election rounds", |m| m.metrics.elections_count),
+	("credits_total", "Tokens credited to the node", |m| m.metrics.credits_issued),
+	("rate_limit_events_total", "Requests rate limited", |m| m.metrics.rate_limit_events),
+	("penalties_total", "Penalty points applied", |m| m.metrics.total_penalties),
+	("connection_failures_total", "Failed connections to other nodes", |m| m.metrics.connection_failures),
+	("chunk_validations_passed_total", "Chunks which passed validation", |m| m.metrics.chunk_validations_passed),
+	("chunk_validations_failed_total", "Chunks which failed validation", |m| m.metrics.chunk_validations_failed),
+	("timeouts_total", "Requests and operations which timed out", |m| m.metrics.timeouts),
+	("lock_contention_events_total", "Lock contention events", |m| m.metrics.lock_contention_events),
+	("sig_verifications_total", "Signatures verified", |m| m.metrics.sig_verifications),
+	("sig_failures_total", "Signature verifications which failed", |m| m.metrics.sig_failures),
+	("proofs_validated_total", "Data proofs validated", |m| m.metrics.proofs_validated),
+	("proofs_rejected_total", "Data proofs rejected", |m| m.metrics.proofs_rejected),
+	("cache_hits_total", "Cache lookups which hit", |m| m.metrics.cache_hits),
+	("cache_misses_total", "Cache lookups which missed", |m| m.metrics.cache_misses),
+	("pow_completions_total", "Proofs of work completed", |m| m.metrics.pow_completions),
+	("transferred_tokens_total", "Tokens transferred", |m| m.metrics.total_transferred),
+	("received_bytes_total", "Bytes received from other nodes", |m| m.metrics.received_bytes_total),
+	("sent_bytes_total", "Bytes sent to other nodes", |m| m.metrics.sent_bytes_total),
+	("gossip_sent_total", "Gossip messages sent", |m| m.metrics.gossip_sent),
+	("gossip_received_total", "Gossip messages received", |m| m.metrics.gossip_received),
+	("routing_additions_total", "Nodes added to the routing table", |m| m.metrics.routing_additions),
+	("routing_removals_total", "Nodes removed from the routing table", |m| m.metrics.routing_removals),
+	("version_mismatches_total", "Peers seen running a different version", |m| m.metrics.version_mismatches),
+	("corruption_events_total", "Corrupt chunks detected", |m| m.metrics.corruption_events),
+];
+
+static GAUGES: &[GaugeSpec] = &[
+	("node_age", "Node age", |m| Some(m.metrics.node_age as i64)),
+	("adults", "Adults in the node's section", |m| Some(m.metrics.adults as i64)),
+	("elders", "Elders in the node's section", |m| Some(m.metrics.elders as i64)),
+	("disk_usage_bytes", "Disk usage reported by the node", |m| m.metrics.disk_usage_bytes.map(|b| b as i64)),
+	("memory_usage_mb", "Memory usage reported by the node", |m| m.metrics.memory_usage_mb.map(|mb| mb as i64)),
+	("quota_bytes", "Storage quota reported by the node", |m| m.metrics.quota_bytes.map(|b| b as i64)),
+	("used_bytes", "Storage used, reported with the quota", |m| m.metrics.used_bytes.map(|b| b as i64)),
+	("quorum", "1 if the node has quorum, otherwise 0", |m| {
+		if m.metrics.quorum_known {
+			Some(m.metrics.quorum as i64)
+		} else {
+			None
+		}
+	}),
+	("quorum_votes", "Votes in the most recent quorum status", |m| Some(m.metrics.quorum_votes as i64)),
+	("chunk_store_bytes", "Space used by the chunk store", |m| Some(m.chunk_store.total_used as i64)),
+	("max_lock_held_ms", "Longest time a lock was held", |m| Some(m.metrics.max_lock_held_ms as i64)),
+	("sig_avg_us", "Mean signature verification time", |m| Some(m.metrics.sig_avg_us as i64)),
+	("pow_avg_ms", "Mean proof of work time", |m| Some(m.metrics.pow_avg_ms as i64)),
+	("pow_avg_difficulty", "Mean proof of work difficulty", |m| Some(m.metrics.pow_avg_difficulty as i64)),
+	("avg_connection_duration_s", "Mean connection duration", |m| Some(m.metrics.avg_connection_duration_s as i64)),
+	("shard_count", "Shards in the network", |m| m.metrics.shard_count.map(|c| c as i64)),
+	("section_joins", "Nodes joining the section in the last epoch", |m| Some(m.metrics.section_joins as i64)),
+	("section_leaves", "Nodes leaving the section in the last epoch", |m| Some(m.metrics.section_leaves as i64)),
+	("replication_factor", "Replication factor reported by the node", |m| m.metrics.replication_factor.map(|r| r as i64)),
+	("protocol_version", "Protocol version negotiated", |m| m.metrics.protocol_version.map(|v| v as i64)),
+];
+
+pub struct MetricsExporter {
+	registry: Registry,
+	counters: Vec<(IntCounterVec, fn(&LogMonitor) -> u64)>,
+	gauges: Vec<(IntGaugeVec, fn(&LogMonitor) -> Option<i64>)>,
+}
+
+impl MetricsExporter {
+	pub fn new() -> Result<MetricsExporter, Error> {
+		let registry = Registry::new_custom(Some(METRICS_PREFIX.to_string()), None)
+			.map_err(to_io_error)?;
+		let labels = [LOGFILE_LABEL, TAGS_LABEL];
+
+		let mut counters = Vec::new();
+		for (name, help, value) in COUNTERS {
+			let counter = IntCounterVec::new(Opts::new(*name, *help), &labels).map_err(to_io_error)?;
+			registry.register(Box::new(counter.clone())).map_err(to_io_error)?;
+			counters.push((counter, *value));
+		}
+
+		let mut gauges = Vec::new();
+		for (name, help, value) in GAUGES {
+			let gauge = IntGaugeVec::new(Opts::new(*name, *help), &labels).map_err(to_io_error)?;
+			registry.register(Box::new(gauge.clone())).map_err(to_io_error)?;
+			gauges.push((gauge, *value));
+		}
+
+		Ok(MetricsExporter {
+			registry,
+			counters,
+			gauges,
+		})
+	}
+
+	///! Copy the current metrics of a monitor into the registry
+	pub fn update(&self, monitor: &LogMonitor) {
+		let tags = monitor.metrics.session_tags.join(",");
+		let labels = [monitor.logfile.as_str(), tags.as_str()];
+		for (counter, value) in &self.counters {
+			let counter = counter.with_label_values(&labels);
+			let value = value(monitor) as i64;
+			if value < counter.get() {
+				counter.reset();
+			}
+			counter.inc_by(value - counter.get());
+		}
+		for (gauge, value) in &self.gauges {
+			if let Some(value) = value(monitor) {
+				gauge.with_label_values(&labels).set(value);
+			}
+		}
+	}
+
+	pub fn registry(&self) -> Registry {
+		self.registry.clone()
+	}
+}
+
+fn to_io_error(e: prometheus::Error) -> Error {
+	Error::new(ErrorKind::Other, format!("prometheus: {}", e))
+}
+
+///! Serve the registry at http://0.0.0.0:<port>/metrics until the app exits
+pub async fn serve_metrics(port: u16, registry: Registry) -> Result<(), Error> {
+	let mut listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, port)).await?;
+	loop {
+		let (stream, _) = listener.accept().await?;
+		let registry = registry.clone();
+		tokio::spawn(async move {
+			if let Err(e) = handle_request(stream, &registry).await {
+				warn!("metrics request failed: {}", e);
+			}
+		});
+	}
+}
+
+async fn handle_request(mut stream: TcpStream, registry: &Registry) -> Result<(), Error> {
+	let mut request = [0u8; 1024];
+	let length = stream.read(&mut request).await?;
+	let request = String::from_utf8_lossy(&request[..length]);
+
+	let response = if request.starts_with("GET /metrics ") {
+		let encoder = TextEncoder::new();
+		let mut body = Vec::<u8>::new();
+		encoder
+			.encode(&registry.gather(), &mut body)
+			.map_err(to_io_error)?;
+		let mut response = format!(
+			"HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+			encoder.format_type(),
+			body.len()
+		)
+		.into_bytes();
+		response.append(&mut body);
+		response
+	} else {
+		"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+			.as_bytes()
+			.to_vec()
+	};
+	stream.write_all(&response).await?;
+	stream.shutdown(std::net::Shutdown::Both)
+}
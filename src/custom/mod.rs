@@ -1,4 +1,11 @@
 pub mod app;
+pub mod config;
+pub mod format_loader;
+pub mod metrics_server;
 pub mod opt;
+pub mod remote;
+pub mod snapshot;
 pub mod ui;
 pub mod ui_debug;
+#[cfg(feature = "web-ui")]
+pub mod web_ui;
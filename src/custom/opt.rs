@@ -6,6 +6,13 @@ pub static MIN_TIMELINE_STEPS: usize = 10;
 
 pub use structopt::StructOpt;
 
+use crate::custom::app::{LineTransform, LogEntry};
+use crate::custom::format_loader::LogFormat;
+use chrono::Duration;
+use regex::Regex;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
 #[derive(StructOpt, Debug)]
 #[structopt(
 	about = "Monitor Safe Network nodes in the terminal.\nNavigate using tab and arrow keys."
@@ -15,6 +22,22 @@ pub struct Opt {
 	#[structopt(short = "l", long, default_value = "100")]
 	pub lines_max: usize,
 
+	/// Maximum number of logfiles (including --remote) to monitor at once
+	#[structopt(long, default_value = "20")]
+	pub max_monitors: usize,
+
+	/// Discard logfile entries older than this many minutes before the most recent
+	#[structopt(long)]
+	pub retention: Option<i64>,
+
+	/// Raise a critical alert when a logfile has no new entries for this many seconds
+	#[structopt(long, default_value = "60")]
+	pub watchdog_timeout: i64,
+
+	/// On quitting, wait up to this many seconds for screenshots and exports to finish writing
+	#[structopt(long, default_value = "5")]
+	pub graceful_shutdown_timeout: u64,
+
 	/// Event update tick in milliseconds
 	#[structopt(long, default_value = "200")]
 	pub tick_rate: u64,
@@ -27,6 +50,10 @@ pub struct Opt {
 	#[structopt(short, long)]
 	pub ignore_existing: bool,
 
+	/// Replace matches of a regex in logfile lines shown, given as 'regex:replacement' (repeatable)
+	#[structopt(long = "transform", number_of_values = 1)]
+	pub transforms: Vec<LineTransform>,
+
 	/// One or more logfiles to monitor
 	#[structopt(name = "LOGFILE")]
 	pub files: Vec<String>,
@@ -38,4 +65,180 @@ pub struct Opt {
 	/// Parses first logfile, prints results to second and shows side-by-side (logtail-crossterm only)
 	#[structopt(long)]
 	pub debug_dashboard: bool,
+
+	/// Initial dashboard layout: 'wide', 'compact' or 'single' (cycle with 'l')
+	#[structopt(long, default_value = "wide")]
+	pub layout_preset: String,
+
+	/// Logfile format: 'safe-vault' or 'custom:<file.toml>' (see src/custom/format_loader.rs)
+	#[structopt(long, default_value = "safe-vault")]
+	pub log_format: LogFormat,
+
+	/// Ignore logfile entries below this level: 'debug', 'info', 'warn' or 'error'
+	#[structopt(long, parse(try_from_str = parse_severity))]
+	pub min_severity: Option<u8>,
+
+	/// Show consecutive repeats of a logfile line once, with a count
+	#[structopt(long)]
+	pub deduplicate: bool,
+
+	/// With --deduplicate, how many of the most recent lines to compare
+	#[structopt(long, default_value = "10")]
+	pub deduplicate_window: usize,
+
+	/// Raise an alert when node disk usage exceeds this many GB
+	#[structopt(long)]
+	pub disk_alert_gb: Option<f64>,
+
+	/// Raise an alert when node memory usage exceeds this many MB
+	#[structopt(long, default_value = "512")]
+	pub memory_warn_mb: u64,
+
+	/// Show the connection failure count in red when it exceeds this
+	#[structopt(long, default_value = "10")]
+	pub conn_failure_threshold: u64,
+
+	/// Raise an alert when this percentage of the node's storage quota is used
+	#[structopt(long, default_value = "80")]
+	pub quota_warn_pct: u64,
+
+	/// Raise an alert when the P99 time to update the node's index exceeds this many microseconds
+	#[structopt(long, default_value = "10000")]
+	pub index_warn_us: u64,
+
+	/// Raise an alert when the node reports holding a lock for longer than this many milliseconds
+	#[structopt(long, default_value = "100")]
+	pub lock_warn_ms: u64,
+
+	/// Raise a critical alert when the replication factor falls below this
+	#[structopt(long, default_value = "3")]
+	pub min_replication: u8,
+
+	/// Regex for logfile banner lines which begin a section (navigate with '[' and ']')
+	#[structopt(long, default_value = r"={3,}\s*(.+?)\s*={3,}", parse(try_from_str = Regex::new))]
+	pub section_pattern: Regex,
+
+	/// Show logfile lines which mention this node ID (or its first 8 characters) in bold
+	#[structopt(long)]
+	pub highlight_own_id: Option<String>,
+
+	/// Tag this monitoring session, shown with the node status and exported metrics (repeatable)
+	#[structopt(long = "tag", number_of_values = 1)]
+	pub tags: Vec<String>,
+
+	/// Shift timelines by an offset such as '+01:00' when logfile times differ from local time
+	#[structopt(long, parse(try_from_str = parse_time_offset))]
+	pub time_offset: Option<Duration>,
+
+	/// Output format when stdout is not a terminal: 'human', 'json' or 'csv'
+	#[structopt(long, default_value = "human")]
+	pub format: OutputFormat,
+
+	/// Measure how fast vdash processes this many synthetic logfile lines per second, then exit
+	#[structopt(long)]
+	pub stress_test: Option<u64>,
+
+	/// How long to run --stress-test for, in seconds
+	#[structopt(long, default_value = "10")]
+	pub stress_duration: i64,
+
+	/// Show ANSI colours embedded in logfile lines, instead of removing them
+	#[structopt(long)]
+	pub ansi_log: bool,
+
+	/// Show the dashboard even when stdout is not a terminal
+	#[structopt(long)]
+	pub force_tui: bool,
+
+	/// Let ctrl-c copy the message of the latest error to the clipboard (uses pbcopy, clip or xclip)
+	#[structopt(long)]
+	pub clipboard: bool,
+
+	/// Stream a logfile from a remote node by connecting to this TCP address
+	#[structopt(long)]
+	pub remote: Option<SocketAddr>,
+
+	/// Serve Prometheus metrics at http://0.0.0.0:<port>/metrics
+	#[structopt(long)]
+	pub emit_metrics_port: Option<u16>,
+
+	/// File to write the network topology of the monitored nodes to in GraphViz DOT format (ctrl-g)
+	#[structopt(long, default_value = "vdash-topology.dot", parse(from_os_str))]
+	pub graphviz_path: PathBuf,
+
+	/// File to write metrics of all logfiles to as JSON, on receipt of SIGUSR1
+	#[structopt(long, default_value = "/tmp/vdash-metrics.json", parse(from_os_str))]
+	pub signal_export_path: PathBuf,
+
+	/// Also write each logfile line to this file as shown in the dashboard, after --min-severity and --transform
+	#[structopt(long, parse(from_os_str))]
+	pub tee: Option<PathBuf>,
+
+	/// Serve a read-only web dashboard at http://0.0.0.0:<port>/
+	#[cfg(feature = "web-ui")]
+	#[structopt(long)]
+	pub web_ui_port: Option<u16>,
+}
+
+///! How logfile entries are written to stdout when the dashboard is not shown
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+	Human,
+	Json,
+	Csv,
+}
+
+impl std::str::FromStr for OutputFormat {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<OutputFormat, String> {
+		match s {
+			"human" => Ok(OutputFormat::Human),
+			"json" => Ok(OutputFormat::Json),
+			"csv" => Ok(OutputFormat::Csv),
+			_ => Err(format!("unknown output format '{}', use 'human', 'json' or 'csv'", s)),
+		}
+	}
+}
+
+///! Error from App::set_opt()
+#[derive(Debug)]
+pub enum OptError {
+	UnknownKey(String),
+	InvalidValue(String, String),
+}
+
+impl std::fmt::Display for OptError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			OptError::UnknownKey(key) => write!(f, "unknown option '{}'", key),
+			OptError::InvalidValue(key, value) => write!(f, "invalid value '{}' for '{}'", value, key),
+		}
+	}
+}
+
+fn parse_time_offset(offset: &str) -> Result<Duration, String> {
+	let invalid = || format!("invalid time offset '{}', expected +HH:MM or -HH:MM", offset);
+	let (sign, hours_minutes) = match offset.chars().next() {
+		Some('+') => (1, &offset[1..]),
+		Some('-') => (-1, &offset[1..]),
+		_ => (1, offset),
+	};
+	let mut parts = hours_minutes.splitn(2, ':');
+	let hours = parts.next().and_then(|h| h.parse::<i64>().ok()).ok_or_else(invalid)?;
+	let minutes = match parts.next() {
+		Some(m) => m.parse::<i64>().map_err(|_| invalid())?,
+		None => 0,
+	};
+	if hours > 23 || minutes > 59 {
+		return Err(invalid());
+	}
+	Ok(Duration::minutes(sign * (hours * 60 + minutes)))
+}
+
+fn parse_severity(level: &str) -> Result<u8, String> {
+	match LogEntry::category_severity_rank(&level.to_uppercase()) {
+		0 => Err(format!("unknown severity level '{}'", level)),
+		rank => Ok(rank),
+	}
 }
@@ -0,0 +1,57 @@
+///! Logfile lines streamed from a remote node over TCP (see --remote)
+///!
+///! Each connection is read by a separate tokio task which sends
+///! newline delimited lines to the main event loop as RemoteLines.
+
+use std::net::SocketAddr;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc::Sender;
+
+// Lines buffered between the connection tasks and the event loop
+pub static REMOTE_CHANNEL_SIZE: usize = 1000;
+
+pub struct RemoteLine {
+	pub source: String, // Name of the monitor for this connection
+	pub line: String,
+}
+
+///! Name of the monitor for a remote node
+pub fn remote_monitor_name(addr: &SocketAddr) -> String {
+	format!("tcp://{}", addr)
+}
+
+///! Read lines from addr until the connection closes
+pub async fn stream_lines(addr: SocketAddr, mut tx: Sender<RemoteLine>) {
+	let source = remote_monitor_name(&addr);
+	let result = match TcpStream::connect(addr).await {
+		Ok(stream) => {
+			let mut reader = BufReader::new(stream);
+			let mut line = String::new();
+			loop {
+				line.clear();
+				match reader.read_line(&mut line).await {
+					Ok(0) => break Ok(()),
+					Ok(_) => {
+						let remote_line = RemoteLine {
+							source: source.clone(),
+							line: line.trim_end_matches(&['\r', '\n'][..]).to_string(),
+						};
+						if tx.send(remote_line).await.is_err() {
+							return; // vdash is exiting
+						}
+					}
+					Err(e) => break Err(e),
+				}
+			}
+		}
+		Err(e) => Err(e),
+	};
+
+	// Report the end of the stream in the logfile view of the monitor
+	let status = match result {
+		Ok(()) => format!("vdash: connection to {} closed", addr),
+		Err(e) => format!("vdash: connection to {} failed: {}", addr, e),
+	};
+	let _ = tx.send(RemoteLine { source, line: status }).await;
+}
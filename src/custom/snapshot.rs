@@ -0,0 +1,61 @@
+///! Serializable copy of the metrics of a monitor
+///!
+///! Used for the JSON written by App::export_all_metrics_json() and served to
+///! the web UI (see --web-ui-port)
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::custom::app::{LogMonitor, TIMELINES};
+
+#[derive(Clone, Serialize)]
+pub struct MetricsSnapshot {
+	pub logfile: String,
+	pub tags: Vec<String>,
+	pub most_recent: Option<String>, // RFC 3339
+	pub node_age: usize,
+	pub adults: usize,
+	pub elders: usize,
+	pub gets: u64,
+	pub puts: u64,
+	pub errors: u64,
+	pub credits: u64,
+	pub disk_usage_bytes: Option<u64>,
+	pub memory_usage_mb: Option<u64>,
+	pub chunk_store_bytes: u64,
+	pub health_score: u8,
+	pub timeline_scale: String,
+	pub timelines: HashMap<String, Vec<u64>>, // Buckets of each timeline at timeline_scale
+}
+
+impl MetricsSnapshot {
+	pub fn from_monitor(monitor: &LogMonitor) -> MetricsSnapshot {
+		let metrics = &monitor.metrics;
+		let timeline_scale = TIMELINES.get(0).map_or("", |(name, _)| name);
+		let mut timelines = HashMap::<String, Vec<u64>>::new();
+		for timeline in metrics.timelines() {
+			if let Some(bucket_set) = timeline.get_bucket_set(timeline_scale) {
+				timelines.insert(timeline.get_name().clone(), bucket_set.buckets().clone());
+			}
+		}
+
+		MetricsSnapshot {
+			logfile: monitor.logfile.clone(),
+			tags: metrics.session_tags.clone(),
+			most_recent: metrics.most_recent.map(|time| time.to_rfc3339()),
+			node_age: metrics.node_age,
+			adults: metrics.adults,
+			elders: metrics.elders,
+			gets: metrics.activity_gets,
+			puts: metrics.activity_puts,
+			errors: metrics.activity_errors,
+			credits: metrics.credits_issued,
+			disk_usage_bytes: metrics.disk_usage_bytes,
+			memory_usage_mb: metrics.memory_usage_mb,
+			chunk_store_bytes: monitor.chunk_store.total_used,
+			health_score: metrics.health_score(),
+			timeline_scale: timeline_scale.to_string(),
+			timelines,
+		}
+	}
+}
@@ -2,7 +2,12 @@
 ///
 /// Edit src/custom/ui.rs to create a customised fork of logtail-dash
 
-use super::app::{TIMELINES, App, DashState, DashViewMain, LogMonitor, DEBUG_WINDOW_NAME};
+use super::app::{
+	TIMELINES, App, BucketSet, HealthIssue, DashState, DashViewMain, LogEntry, LogMonitor, NodeAgebracket, Notification,
+	NotificationLevel, PredictionMethod, TimelineSet, TrendDirection, DEBUG_WINDOW_NAME, HIGH_BEACON_LATENCY_MS,
+	HIGH_ROUTING_CHURN_PER_MINUTE, HIGH_CHURN_RATIO, HIGH_POW_MS, MIN_CACHE_HIT_RATE,
+	MAX_SIG_FAILURE_RATE, HIGH_TIMEOUTS_PER_MINUTE, MAX_PROOF_REJECTION_RATE,
+};
 use super::ui_debug::draw_dashboard as debug_draw_dashboard;
 
 #[path = "../widgets/mod.rs"]
@@ -11,48 +16,405 @@ use self::widgets::sparkline::Sparkline2;
 use self::widgets::gauge::Gauge2;
 use std::collections::HashMap;
 
+use chrono::Utc;
+use regex::Regex;
+
 use tui::{
 	backend::Backend,
 	layout::{Constraint, Direction, Layout, Rect},
 	style::{Color, Modifier, Style},
-	text::{Spans},
-	widgets::{Block, Borders, List, ListItem},
+	text::{Span, Spans},
+	widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+	buffer::Buffer,
 	Frame,
 };
 
 pub fn draw_dashboard<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+	app.dash_state.terminal_size = Some(f.size());
 	match app.dash_state.main_view {
 		DashViewMain::DashSummary => {} //draw_summary_dash(f, dash_state, monitors),
-		DashViewMain::DashNode => draw_node_dash(f, &mut app.dash_state, &mut app.monitors),
+		DashViewMain::DashNode => {
+			let monitors_status = format!("{}/{} monitors", app.monitor_count(), app.config.max_monitors);
+			draw_node_dash(f, &mut app.dash_state, &mut app.monitors, &monitors_status)
+		}
 		DashViewMain::DashDebug => debug_draw_dashboard(f, &mut app.dash_state, &mut app.monitors),
+		DashViewMain::DashHealth => draw_health_dash(f, &app.health_check()),
+		DashViewMain::DashSearch => draw_search_dash(f, &app.dash_state, &app.monitors),
+		DashViewMain::DashNetwork => draw_network_dash(f, &app.partition_monitors_by_agebracket()),
+	}
+
+	if app.dash_state.help_overlay {
+		draw_help_overlay(f);
+	}
+
+	let command_text = match &app.dash_state.command_line {
+		Some(command) => Some(format!(":{}", command)),
+		None => app.dash_state.command_message.clone(),
+	};
+	if let Some(text) = command_text {
+		draw_command_line(f, &text);
+	}
+}
+
+///! Keyboard shortcuts, shown by '?' (keep in step with src/bin/*.rs)
+pub static KEY_BINDINGS: &[(&str, &str)] = &[
+	("?", "Show or hide this help"),
+	("q", "Quit"),
+	("left/right, tab", "Previous/next node"),
+	("up/down", "Scroll the logfile"),
+	("[ and ]", "Previous/next logfile section"),
+	("i, + and o, -", "Zoom timeline in/out"),
+	("l", "Next dashboard layout"),
+	("m", "Merge all nodes into one view"),
+	("n", "Show what was decoded from each logfile line"),
+	("c", "Show nodes side by side in columns"),
+	("b", "Show a sidebar summarising every node"),
+	("v", "Node view"),
+	("h", "Health report"),
+	("f", "Focus on the node with the worst health"),
+	("w", "Network overview by node role"),
+	("g", "Debug view"),
+	("Esc", "Back to the previous view"),
+	(":", "Command line, e.g. 'set lines_max=500'"),
+	("/", "Search all logfiles (Enter shows a result)"),
+	("ctrl-a", "Archive the logfile content to .log.gz"),
+	("ctrl-p", "Save a screenshot to vdash-<time>.ans"),
+	("ctrl-c", "Copy the latest error to the clipboard (see --clipboard)"),
+	("ctrl-g", "Save the network topology to vdash-topology.dot"),
+	("ctrl-f", "Count the lines of this logfile matching a regex"),
+];
+
+fn draw_help_overlay<B: Backend>(f: &mut Frame<B>) {
+	let size = f.size();
+	let width = 60.min(size.width);
+	let height = (KEY_BINDINGS.len() as u16 + 2).min(size.height);
+	let area = Rect::new(
+		size.x + (size.width - width) / 2,
+		size.y + (size.height - height) / 2,
+		width,
+		height,
+	);
+
+	let items: Vec<ListItem> = KEY_BINDINGS
+		.iter()
+		.map(|(key, description)| {
+			ListItem::new(vec![Spans::from(vec![
+				Span::styled(format!("{:<18}", key), Style::default().fg(Color::Yellow)),
+				Span::from(*description),
+			])])
+		})
+		.collect();
+	let help_widget = List::new(items).block(
+		Block::default()
+			.borders(Borders::ALL)
+			.title("Keyboard Shortcuts"),
+	);
+	f.render_widget(Clear, area);
+	f.render_widget(help_widget, area);
+}
+
+///! Convert a rendered buffer to text, with ANSI escape sequences for colours and modifiers
+pub fn buffer_to_ansi(buffer: &Buffer) -> String {
+	let mut ansi = String::new();
+	let width = buffer.area.width as usize;
+	for (row, cells) in buffer.content.chunks(width.max(1)).enumerate() {
+		if row > 0 {
+			ansi.push_str("\x1b[0m\n");
+		}
+		let mut current: Option<(Color, Color, Modifier)> = None;
+		for cell in cells {
+			let style = (cell.fg, cell.bg, cell.modifier);
+			if current != Some(style) {
+				ansi.push_str(&ansi_style(cell.fg, cell.bg, cell.modifier));
+				current = Some(style);
+			}
+			ansi.push_str(&cell.symbol);
+		}
+	}
+	ansi.push_str("\x1b[0m\n");
+	ansi
+}
+
+fn ansi_style(fg: Color, bg: Color, modifier: Modifier) -> String {
+	let mut codes = vec![String::from("0")];
+	for (flag, code) in &[
+		(Modifier::BOLD, "1"),
+		(Modifier::DIM, "2"),
+		(Modifier::ITALIC, "3"),
+		(Modifier::UNDERLINED, "4"),
+		(Modifier::REVERSED, "7"),
+	] {
+		if modifier.contains(*flag) {
+			codes.push(code.to_string());
+		}
+	}
+	if let Some(code) = ansi_colour(fg, 30) {
+		codes.push(code);
+	}
+	if let Some(code) = ansi_colour(bg, 40) {
+		codes.push(code);
+	}
+	format!("\x1b[{}m", codes.join(";"))
+}
+
+// base is 30 for foreground and 40 for background colours
+fn ansi_colour(colour: Color, base: u8) -> Option<String> {
+	let code = match colour {
+		Color::Reset => return None,
+		Color::Black => base,
+		Color::Red => base + 1,
+		Color::Green => base + 2,
+		Color::Yellow => base + 3,
+		Color::Blue => base + 4,
+		Color::Magenta => base + 5,
+		Color::Cyan => base + 6,
+		Color::Gray => base + 7,
+		Color::DarkGray => base + 60,
+		Color::LightRed => base + 61,
+		Color::LightGreen => base + 62,
+		Color::LightYellow => base + 63,
+		Color::LightBlue => base + 64,
+		Color::LightMagenta => base + 65,
+		Color::LightCyan => base + 66,
+		Color::White => base + 67,
+		Color::Rgb(r, g, b) => return Some(format!("{};2;{};{};{}", base + 8, r, g, b)),
+		Color::Indexed(i) => return Some(format!("{};5;{}", base + 8, i)),
+	};
+	Some(code.to_string())
+}
+
+lazy_static::lazy_static! {
+	static ref ANSI_SGR_PATTERN: Regex =
+		Regex::new(r"\x1b\[(?P<codes>[0-9;]*)m").expect("The regex failed to compile. This is a bug.");
+}
+
+///! Remove ANSI colour and style escape sequences from line
+pub fn strip_ansi(line: &str) -> String {
+	ANSI_SGR_PATTERN.replace_all(line, "").into_owned()
+}
+
+///! Convert a line containing ANSI colour and style escape sequences to styled spans
+pub fn ansi_to_spans(line: &str) -> Spans<'static> {
+	let mut spans = Vec::<Span>::new();
+	let mut style = Style::default();
+	let mut start = 0;
+	for captures in ANSI_SGR_PATTERN.captures_iter(line) {
+		let escape = captures.get(0).unwrap();
+		if escape.start() > start {
+			spans.push(Span::styled(line[start..escape.start()].to_string(), style));
+		}
+		style = apply_sgr_codes(style, &captures["codes"]);
+		start = escape.end();
 	}
+	if start < line.len() {
+		spans.push(Span::styled(line[start..].to_string(), style));
+	}
+	Spans::from(spans)
+}
+
+// Update style with the ';' separated codes of an ANSI SGR sequence (see ansi_style())
+fn apply_sgr_codes(mut style: Style, codes: &str) -> Style {
+	let codes: Vec<u8> = codes.split(';').map(|code| code.parse::<u8>().unwrap_or(0)).collect();
+	let mut i = 0;
+	while i < codes.len() {
+		match codes[i] {
+			0 => style = Style::default(),
+			1 => style = style.add_modifier(Modifier::BOLD),
+			2 => style = style.add_modifier(Modifier::DIM),
+			3 => style = style.add_modifier(Modifier::ITALIC),
+			4 => style = style.add_modifier(Modifier::UNDERLINED),
+			7 => style = style.add_modifier(Modifier::REVERSED),
+			22 => style = style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+			23 => style = style.remove_modifier(Modifier::ITALIC),
+			24 => style = style.remove_modifier(Modifier::UNDERLINED),
+			27 => style = style.remove_modifier(Modifier::REVERSED),
+			code @ 30..=37 | code @ 90..=97 => style = style.fg(ansi_code_colour(code - 30)),
+			39 => style = style.fg(Color::Reset),
+			code @ 40..=47 | code @ 100..=107 => style = style.bg(ansi_code_colour(code - 40)),
+			49 => style = style.bg(Color::Reset),
+			code @ 38 | code @ 48 => {
+				let colour = match codes.get(i + 1) {
+					Some(5) => codes.get(i + 2).map(|&index| {
+						i += 2;
+						Color::Indexed(index)
+					}),
+					Some(2) if i + 4 < codes.len() => {
+						i += 4;
+						Some(Color::Rgb(codes[i - 2], codes[i - 1], codes[i]))
+					}
+					_ => None,
+				};
+				if let Some(colour) = colour {
+					style = if code == 38 { style.fg(colour) } else { style.bg(colour) };
+				}
+			}
+			_ => {}
+		}
+		i += 1;
+	}
+	style
+}
+
+// The colour of an ANSI code less its base (see ansi_colour())
+fn ansi_code_colour(offset: u8) -> Color {
+	match offset {
+		0 => Color::Black,
+		1 => Color::Red,
+		2 => Color::Green,
+		3 => Color::Yellow,
+		4 => Color::Blue,
+		5 => Color::Magenta,
+		6 => Color::Cyan,
+		7 => Color::Gray,
+		60 => Color::DarkGray,
+		61 => Color::LightRed,
+		62 => Color::LightGreen,
+		63 => Color::LightYellow,
+		64 => Color::LightBlue,
+		65 => Color::LightMagenta,
+		66 => Color::LightCyan,
+		_ => Color::White,
+	}
+}
+
+// Overlays the bottom line of the terminal
+fn draw_command_line<B: Backend>(f: &mut Frame<B>, text: &str) {
+	let size = f.size();
+	if size.height < 1 {
+		return;
+	}
+	let area = Rect::new(size.x, size.bottom() - 1, size.width, 1);
+	let text = format!("{:<width$}", text, width = size.width as usize);
+	let command_widget = Paragraph::new(Spans::from(text))
+		.style(Style::default().fg(Color::Black).bg(Color::White));
+	f.render_widget(command_widget, area);
+}
+
+fn draw_health_dash<B: Backend>(f: &mut Frame<B>, issues: &[HealthIssue]) {
+	let mut items = Vec::<ListItem>::new();
+	if issues.is_empty() {
+		items.push(
+			ListItem::new(vec![Spans::from("No problems found")])
+				.style(Style::default().fg(Color::Green)),
+		);
+	}
+	for issue in issues {
+		let colour = match issue.kind.severity() {
+			NotificationLevel::Critical => Color::Red,
+			NotificationLevel::Warning => Color::Magenta,
+			NotificationLevel::Info => Color::Blue,
+		};
+		let s = format!("{:<16} {:<40} {}", format!("{:?}", issue.kind), issue.monitor, issue.message);
+		items.push(ListItem::new(vec![Spans::from(s)]).style(Style::default().fg(colour)));
+	}
+
+	let health_widget = List::new(items).block(
+		Block::default()
+			.borders(Borders::ALL)
+			.title(format!("Health Report ({} issues, Esc to go back)", issues.len())),
+	);
+	f.render_widget(health_widget, f.size());
+}
+
+fn draw_network_dash<B: Backend>(f: &mut Frame<B>, partition: &HashMap<NodeAgebracket, Vec<&LogMonitor>>) {
+	let mut items = vec![ListItem::new(vec![Spans::from(format!(
+		"{:<10} {:>6} {:>10} {:>10} {:>10} {:>8}",
+		"Role", "Nodes", "GETS", "PUTS", "ERRORS", "Health"
+	))])
+	.style(Style::default().fg(Color::Yellow))];
+
+	let brackets = [NodeAgebracket::Elder, NodeAgebracket::Adult, NodeAgebracket::Infant, NodeAgebracket::Unknown];
+	for bracket in brackets.iter() {
+		let monitors = match partition.get(bracket) {
+			Some(monitors) => monitors,
+			None => continue,
+		};
+		let gets: u64 = monitors.iter().map(|monitor| monitor.metrics.activity_gets).sum();
+		let puts: u64 = monitors.iter().map(|monitor| monitor.metrics.activity_puts).sum();
+		let errors: u64 = monitors.iter().map(|monitor| monitor.metrics.activity_errors).sum();
+		let health: u64 = monitors.iter().map(|monitor| monitor.metrics.health_score() as u64).sum();
+		let s = format!(
+			"{:<10} {:>6} {:>10} {:>10} {:>10} {:>7}%",
+			format!("{:?}", bracket),
+			monitors.len(),
+			gets,
+			puts,
+			errors,
+			health / monitors.len() as u64
+		);
+		items.push(ListItem::new(vec![Spans::from(s)]).style(Style::default().fg(Color::Blue)));
+	}
+
+	let node_count: usize = partition.values().map(|monitors| monitors.len()).sum();
+	let network_widget = List::new(items).block(
+		Block::default()
+			.borders(Borders::ALL)
+			.title(format!("Network ({} nodes, Esc to go back)", node_count)),
+	);
+	f.render_widget(network_widget, f.size());
+}
+
+fn draw_search_dash<B: Backend>(f: &mut Frame<B>, dash_state: &DashState, monitors: &HashMap<String, LogMonitor>) {
+	let mut items = Vec::<ListItem>::new();
+	let mut selected_row = None;
+	let mut result = 0;
+	for (logfile, indices) in dash_state.search_results.iter() {
+		items.push(
+			ListItem::new(vec![Spans::from(format!("{} ({} matches)", logfile, indices.len()))])
+				.style(Style::default().fg(Color::Yellow)),
+		);
+		for index in indices {
+			let line = monitors
+				.get(logfile)
+				.and_then(|monitor| monitor.content.items.get(*index))
+				.map_or("", |line| line.as_str());
+			if result == dash_state.search_selected {
+				selected_row = Some(items.len());
+			}
+			items.push(ListItem::new(vec![Spans::from(format!("{:>6}: {}", index + 1, line))]));
+			result += 1;
+		}
+	}
+	if items.is_empty() {
+		items.push(ListItem::new(vec![Spans::from("No matches")]));
+	}
+
+	let mut state = ListState::default();
+	state.select(selected_row);
+	let search_widget = List::new(items)
+		.block(
+			Block::default()
+				.borders(Borders::ALL)
+				.title(format!("Search '{}' ({} matches, Esc to go back)", dash_state.search_pattern, result)),
+		)
+		.highlight_style(Style::default().bg(Color::LightGreen).add_modifier(Modifier::BOLD));
+	f.render_stateful_widget(search_widget, f.size(), &mut state);
 }
 
 fn draw_node_dash<B: Backend>(
 	f: &mut Frame<B>,
 	dash_state: &mut DashState,
 	monitors: &mut HashMap<String, LogMonitor>,
+	monitors_status: &str,
 ) {
-	// Horizonatal bands:
-	let constraints = [
-		Constraint::Length(12), // Stats summary and graphs
-		Constraint::Length(18), // Timeline
-		Constraint::Min(0),     // Bottom panel
-	];
+	let mut size = f.size();
+	if dash_state.mini_map {
+		let chunks = Layout::default()
+			.direction(Direction::Horizontal)
+			.constraints([Constraint::Min(0), Constraint::Length(SIDEBAR_WIDTH)].as_ref())
+			.split(size);
+		draw_mini_map(f, chunks[1], dash_state, monitors);
+		size = chunks[0];
+	}
 
-	let size = f.size();
-	let chunks = Layout::default()
-		.direction(Direction::Vertical)
-		.constraints(constraints.as_ref())
-		.split(size);
+	if dash_state.column_mode && draw_node_columns(f, size, dash_state, monitors, monitors_status) {
+		return;
+	}
 
 	for entry in monitors.into_iter() {
 		let (logfile, mut monitor) = entry;
 		if monitor.has_focus {
-			// Stats and Graphs / Timeline / Logfile
-			draw_node(f, chunks[0], dash_state, &mut monitor);
-			draw_timeline(f, chunks[1], dash_state, &mut monitor);
-			draw_bottom_panel(f, chunks[2], dash_state, &logfile, &mut monitor);
+			draw_node_column(f, size, dash_state, logfile, &mut monitor, monitors_status);
 			return;
 		}
 	}
@@ -60,7 +422,156 @@ fn draw_node_dash<B: Backend>(
 	draw_debug_window(f, size, dash_state);
 }
 
-fn draw_node<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &mut DashState, monitor: &mut LogMonitor) {
+const SIDEBAR_WIDTH: u16 = 18; // Borders, health, SIDEBAR_BUCKETS and an error count
+const SIDEBAR_BUCKETS: usize = 10;
+const SPARK_SYMBOLS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+// A row for every node: a health indicator, recent GETS + PUTS and the error count
+fn draw_mini_map<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &DashState, monitors: &HashMap<String, LogMonitor>) {
+	let timeline_name = TIMELINES.get(dash_state.active_timeline).map_or("", |(name, _)| name);
+	let mut rows: Vec<&LogMonitor> = monitors.values().filter(|monitor| !monitor.is_debug_dashboard_log).collect();
+	rows.sort_by_key(|monitor| monitor.index);
+
+	let items: Vec<ListItem> = rows
+		.iter()
+		.map(|monitor| {
+			let health = monitor.metrics.health_score();
+			let health_colour = if health >= 80 {
+				Color::Green
+			} else if health >= 50 {
+				Color::Yellow
+			} else {
+				Color::Red
+			};
+
+			let mut activity = vec![0u64; SIDEBAR_BUCKETS];
+			for timeline in &[&monitor.metrics.gets_timeline, &monitor.metrics.puts_timeline] {
+				if let Some(bucket_set) = timeline.get_bucket_set(timeline_name) {
+					let recent = buckets_right_justify(bucket_set.buckets(), SIDEBAR_BUCKETS as u16);
+					let offset = SIDEBAR_BUCKETS - recent.len();
+					for (i, value) in recent.iter().enumerate() {
+						activity[offset + i] += value;
+					}
+				}
+			}
+			let max = activity.iter().cloned().max().unwrap_or(0).max(1);
+			let sparkline: String = activity
+				.iter()
+				.map(|&value| SPARK_SYMBOLS[(value * (SPARK_SYMBOLS.len() as u64 - 1) / max) as usize])
+				.collect();
+
+			let style = if monitor.has_focus { Style::default().add_modifier(Modifier::BOLD) } else { Style::default() };
+			ListItem::new(Spans::from(vec![
+				Span::styled("●", Style::default().fg(health_colour)),
+				Span::styled(sparkline, style),
+				Span::styled(format!("{:>4}", monitor.metrics.activity_errors.min(9999)), style.fg(Color::Red)),
+			]))
+		})
+		.collect();
+
+	let sidebar = List::new(items).block(Block::default().borders(Borders::ALL).title("Nodes"));
+	f.render_widget(sidebar, area);
+}
+
+const MAX_COLUMNS: usize = 4;
+const MIN_COLUMN_WIDTH: u16 = 80;
+
+// Draw up to MAX_COLUMNS nodes side by side, including the one with focus
+//
+// Returns false, with a warning on the status line, if the terminal is too narrow
+fn draw_node_columns<B: Backend>(
+	f: &mut Frame<B>,
+	area: Rect,
+	dash_state: &mut DashState,
+	monitors: &mut HashMap<String, LogMonitor>,
+	monitors_status: &str,
+) -> bool {
+	let mut columns: Vec<(&String, &mut LogMonitor)> = monitors
+		.iter_mut()
+		.filter(|(_, monitor)| !monitor.is_debug_dashboard_log)
+		.collect();
+	columns.sort_by_key(|(_, monitor)| monitor.index);
+	let count = columns.len().min(MAX_COLUMNS);
+	if count == 0 {
+		return false;
+	}
+	if area.width < MIN_COLUMN_WIDTH * count as u16 {
+		dash_state.command_message = Some(format!(
+			"warning: {} columns need a terminal {} wide",
+			count,
+			MIN_COLUMN_WIDTH * count as u16
+		));
+		return false;
+	}
+
+	// The window of columns which includes the node with focus
+	let focus = columns.iter().position(|(_, monitor)| monitor.has_focus).unwrap_or(0);
+	let first = focus.min(columns.len() - count);
+	let constraints = vec![Constraint::Ratio(1, count as u32); count];
+	let chunks = Layout::default()
+		.direction(Direction::Horizontal)
+		.constraints(constraints.as_ref())
+		.split(area);
+	for ((logfile, monitor), chunk) in columns.into_iter().skip(first).take(count).zip(chunks) {
+		draw_node_column(f, chunk, dash_state, logfile, monitor, monitors_status);
+	}
+	true
+}
+
+fn draw_node_column<B: Backend>(
+	f: &mut Frame<B>,
+	area: Rect,
+	dash_state: &mut DashState,
+	logfile: &String,
+	monitor: &mut LogMonitor,
+	monitors_status: &str,
+) {
+	let status_items = node_stats_items(&monitor);
+
+	// Horizonatal bands (see DashState::layout_preset()), with the
+	// node band extended if needed to show all the status items:
+	let show_node = dash_state.dash_vertical.show_node;
+	let show_timeline = dash_state.dash_vertical.show_timeline;
+	let node_height = std::cmp::max(
+		dash_state.dash_vertical.node_height,
+		status_items.len() as u16 + 2,
+	);
+	let mut constraints = Vec::<Constraint>::new();
+	if show_node {
+		constraints.push(Constraint::Length(node_height)); // Stats summary and graphs
+	}
+	if show_timeline {
+		constraints.push(Constraint::Length(dash_state.dash_vertical.timeline_height)); // Timeline
+	}
+	constraints.push(Constraint::Min(0)); // Bottom panel
+
+	let chunks = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints(constraints.as_ref())
+		.split(area);
+
+	// Stats and Graphs / Timeline / Logfile
+	let mut next_chunk = 0;
+	if show_node {
+		draw_node(f, chunks[next_chunk], dash_state, monitor, status_items);
+		next_chunk += 1;
+	}
+	if show_timeline {
+		draw_timeline(f, chunks[next_chunk], dash_state, monitor);
+		next_chunk += 1;
+	}
+	draw_bottom_panel(f, chunks[next_chunk], dash_state, logfile, monitor, monitors_status);
+}
+
+static CORRUPTION_FLASH_SECONDS: i64 = 10; // Flash the corruption counter after a new event
+
+fn draw_node<B: Backend>(
+	f: &mut Frame<B>,
+	area: Rect,
+	dash_state: &mut DashState,
+	monitor: &mut LogMonitor,
+	status_items: Vec<ListItem>,
+) {
 	// Columns:
 	let constraints = [
 		Constraint::Length(40), // Stats summary
@@ -72,14 +583,164 @@ fn draw_node<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &mut DashStat
 		.constraints(constraints.as_ref())
 		.split(area);
 
-	draw_node_stats(f, chunks[0], monitor);
-	draw_node_storage(f, chunks[1], dash_state, monitor);
+	draw_node_stats(f, chunks[0], monitor, status_items);
+
+	// Sparklines below the chunk store, for activity which has been seen
+	let show_syncs = !monitor.metrics.sync_events.is_empty();
+	let show_transfers = !monitor.metrics.token_transfers.is_empty();
+	let show_cache = monitor.metrics.cache_hit_rate().is_some();
+	let show_health = !monitor.metrics.log_history.is_empty();
+	let mut constraints = vec![Constraint::Min(0)];
+	constraints.resize(
+		1 + show_syncs as usize + show_transfers as usize + show_cache as usize + show_health as usize,
+		Constraint::Length(NODE_SPARKLINE_HEIGHT),
+	);
+	let graphs = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints(constraints.as_ref())
+		.split(chunks[1]);
+	draw_node_storage(f, graphs[0], dash_state, monitor);
+
+	let mut next_graph = 1;
+	if show_syncs {
+		draw_node_sparkline(f, graphs[next_graph], dash_state, &monitor.metrics.sync_timeline, "SYNCS", Color::Cyan);
+		next_graph += 1;
+	}
+	if show_transfers {
+		let title = format!("TRANSFERS ({} tokens)", monitor.metrics.total_transferred);
+		draw_node_sparkline(f, graphs[next_graph], dash_state, &monitor.metrics.transfers_timeline, &title, Color::Green);
+		next_graph += 1;
+	}
+	if let Some(rate) = monitor.metrics.cache_hit_rate() {
+		let title = format!("CACHE LOOKUPS ({:.0}% hits)", rate * 100.0);
+		draw_node_sparkline(f, graphs[next_graph], dash_state, &monitor.metrics.cache_timeline, &title, Color::Magenta);
+		next_graph += 1;
+	}
+	if show_health {
+		draw_health_sparkline(f, graphs[next_graph], monitor);
+	}
 }
 
-fn draw_node_stats<B: Backend>(f: &mut Frame<B>, area: Rect, monitor: &mut LogMonitor) {
+// Health score after each recent logfile entry, one entry per column
+fn draw_health_sparkline<B: Backend>(f: &mut Frame<B>, area: Rect, monitor: &LogMonitor) {
+	let block = Block::default().borders(Borders::ALL);
+	let inner = block.inner(area);
+	f.render_widget(block, area);
+
+	let scores: Vec<u64> = monitor
+		.metrics
+		.compute_score_history(inner.width as usize)
+		.iter()
+		.map(|&score| score as u64)
+		.collect();
+	let title = format!("HEALTH ({}%)", monitor.metrics.health_score());
+	let sparkline = Sparkline2::default()
+		.block(Block::default().title(title))
+		.data(&scores)
+		.max(100)
+		.style(Style::default().fg(Color::LightGreen));
+	f.render_widget(sparkline, inner);
+}
+
+const NODE_SPARKLINE_HEIGHT: u16 = 4;
+
+fn draw_node_sparkline<B: Backend>(
+	f: &mut Frame<B>,
+	area: Rect,
+	dash_state: &DashState,
+	timeline: &TimelineSet,
+	title: &str,
+	colour: Color,
+) {
+	let active_timeline_name = match TIMELINES.get(dash_state.active_timeline) {
+		Some((name, _)) => name,
+		None => return,
+	};
+	let block = Block::default().borders(Borders::ALL);
+	let inner = block.inner(area);
+	f.render_widget(block, area);
+
+	if let Some(bucket_set) = timeline.get_bucket_set(active_timeline_name) {
+		let annotations = bucket_set.annotate(&[]);
+		draw_sparkline(f, inner, bucket_set, &annotations, title, colour);
+	}
+}
+
+fn draw_node_stats<B: Backend>(
+	f: &mut Frame<B>,
+	area: Rect,
+	monitor: &mut LogMonitor,
+	items: Vec<ListItem>,
+) {
+	let mut heading = if monitor.is_aggregate {
+		format!("{} Status", monitor.logfile)
+	} else {
+		format!("Node {:>2} Status", monitor.index + 1)
+	};
+	if let Some(prefix) = &monitor.metrics.genesis_prefix {
+		heading = format!("{} ({})", heading, prefix);
+	}
+	let mut title = vec![Span::from(heading)];
+	if !monitor.metrics.session_tags.is_empty() {
+		title.push(Span::styled(
+			format!(" [{}]", monitor.metrics.session_tags.join(", ")),
+			Style::default().fg(Color::Cyan),
+		));
+	}
+	if monitor.metrics.quorum_known {
+		let (badge, colour) = if monitor.metrics.quorum {
+			(" [QUORUM]", Color::Green)
+		} else {
+			(" [NO QUORUM]", Color::Red)
+		};
+		title.push(Span::styled(
+			badge,
+			Style::default().fg(colour).add_modifier(Modifier::BOLD),
+		));
+	}
+	if monitor.metrics.version_mismatches > 0 {
+		title.push(Span::styled(
+			" [VERSION MISMATCH]",
+			Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+		));
+	}
+	if monitor.metrics.connection_failures > 0 {
+		let colour = if monitor.metrics.connection_failures_high() { Color::Red } else { Color::Yellow };
+		title.push(Span::styled(
+			format!(" [CONN FAILURES: {}]", monitor.metrics.connection_failures),
+			Style::default().fg(colour).add_modifier(Modifier::BOLD),
+		));
+	}
+	if monitor.metrics.corruption_events > 0 {
+		let mut style = Style::default().fg(Color::Red).add_modifier(Modifier::BOLD);
+		let latest = monitor.metrics.corruptions.last().and_then(|corruption| corruption.time);
+		if latest.map_or(false, |time| Utc::now() - time < chrono::Duration::seconds(CORRUPTION_FLASH_SECONDS)) {
+			style = style.add_modifier(Modifier::RAPID_BLINK);
+		}
+		title.push(Span::styled(format!(" [CORRUPTION {}]", monitor.metrics.corruption_events), style));
+	}
+	let monitor_widget = List::new(items).block(
+		Block::default()
+			.borders(Borders::ALL)
+			.title(Spans::from(title)),
+	);
+	f.render_stateful_widget(monitor_widget, area, &mut monitor.metrics_status.state);
+}
+
+fn node_stats_items<'a>(monitor: &LogMonitor) -> Vec<ListItem<'a>> {
 	// TODO maybe add items to monitor.metrics_status and make items from that as in draw_logfile()
 	let mut items = Vec::<ListItem>::new();
 	push_subheading(&mut items, &"Node".to_string());
+	if monitor.metrics.startup_in_progress() {
+		if let Some(step) = monitor.metrics.startup_steps.last() {
+			push_coloured_metric(
+				&mut items,
+				&"Startup".to_string(),
+				&format!("{} {}", capacity_bar(1.0 - monitor.metrics.startup_progress()), step.name),
+				Color::Yellow,
+			);
+		}
+	}
 	push_metric(
 		&mut items,
 		&"Role".to_string(),
@@ -120,7 +781,229 @@ fn draw_node_stats<B: Backend>(f: &mut Frame<B>, area: Rect, monitor: &mut LogMo
 		&monitor.metrics.activity_errors.to_string(),
 	);
 
+	if monitor.metrics.timeouts > 0 {
+		let per_minute = monitor.metrics.timeouts_per_minute();
+		let colour = if per_minute >= HIGH_TIMEOUTS_PER_MINUTE { Color::Red } else { Color::Yellow };
+		push_coloured_metric(
+			&mut items,
+			&"Timeouts".to_string(),
+			&format!("{}/min ({})", per_minute, monitor.metrics.timeouts),
+			colour,
+		);
+	}
+
+	if monitor.metrics.rate_limit_events > 0 {
+		push_metric(
+			&mut items,
+			&"Throttled".to_string(),
+			&format!("{}/min", monitor.metrics.throttle_per_minute()),
+		);
+	}
+
+	if monitor.metrics.total_penalties > 0 {
+		push_coloured_metric(
+			&mut items,
+			&"Penalties".to_string(),
+			&monitor.metrics.total_penalties.to_string(),
+			Color::Yellow,
+		);
+	}
+
+	if let Some(nat_type) = &monitor.metrics.nat_type {
+		let colour = if nat_type == "Symmetric" { Color::Magenta } else { Color::Blue };
+		push_coloured_metric(&mut items, &"NAT".to_string(), nat_type, colour);
+	}
+
+	if let Some(factor) = monitor.metrics.replication_factor {
+		let colour = match factor {
+			0 | 1 => Color::Red,
+			2 => Color::Yellow,
+			_ => Color::Green,
+		};
+		push_coloured_metric(&mut items, &"Replication".to_string(), &factor.to_string(), colour);
+	}
+	push_metric(
+		&mut items,
+		&"Health".to_string(),
+		&format!("{}%", monitor.metrics.health_score()),
+	);
+
+	if monitor.metrics.credits_issued > 0 {
+		push_metric(
+			&mut items,
+			&"Credits".to_string(),
+			&monitor.metrics.credits_issued.to_string(),
+		);
+	}
+
+	if let Some(rate) = monitor.metrics.sync_success_rate() {
+		push_metric(
+			&mut items,
+			&"Sync Rate".to_string(),
+			&format!("{:.0}% of {}", rate * 100.0, monitor.metrics.sync_events.len()),
+		);
+	}
+
+	if let Some(rate) = monitor.metrics.chunk_validation_rate() {
+		push_metric(
+			&mut items,
+			&"Validation".to_string(),
+			&format!("{:.1}% passed", rate * 100.0),
+		);
+	}
+
+	if let Some(rate) = monitor.metrics.cache_hit_rate() {
+		let colour = if rate < MIN_CACHE_HIT_RATE { Color::Yellow } else { Color::Blue };
+		push_coloured_metric(
+			&mut items,
+			&"Cache Hits".to_string(),
+			&format!("{:.0}%", rate * 100.0),
+			colour,
+		);
+	}
+
+	if let (Some(p50), Some(p99)) = (monitor.metrics.index_p50_us(), monitor.metrics.index_p99_us()) {
+		let colour = if p99 > monitor.metrics.index_warn_us { Color::Yellow } else { Color::Blue };
+		push_coloured_metric(
+			&mut items,
+			&"Index P50/99".to_string(),
+			&format!("{}µs / {}µs", p50, p99),
+			colour,
+		);
+	}
+
+	if monitor.metrics.lock_contention_events > 0 || monitor.metrics.max_lock_held_ms > 0 {
+		let colour = if monitor.metrics.max_lock_held_ms > monitor.metrics.lock_warn_ms { Color::Yellow } else { Color::Blue };
+		push_coloured_metric(
+			&mut items,
+			&"Locks".to_string(),
+			&format!(
+				"{} contended, max {}ms",
+				monitor.metrics.lock_contention_events, monitor.metrics.max_lock_held_ms
+			),
+			colour,
+		);
+	}
+
+	if let Some(failure_rate) = monitor.metrics.sig_failure_rate() {
+		let colour = if failure_rate > MAX_SIG_FAILURE_RATE { Color::Red } else { Color::Blue };
+		push_coloured_metric(
+			&mut items,
+			&"Signatures".to_string(),
+			&format!("{:.0}µs, {} failed", monitor.metrics.sig_avg_us, monitor.metrics.sig_failures),
+			colour,
+		);
+	}
+
+	if let Some(acceptance_rate) = monitor.metrics.proof_acceptance_rate() {
+		let colour = if 1.0 - acceptance_rate > MAX_PROOF_REJECTION_RATE { Color::Red } else { Color::Blue };
+		push_coloured_metric(
+			&mut items,
+			&"Data Proofs".to_string(),
+			&format!("{:.1}% accepted", acceptance_rate * 100.0),
+			colour,
+		);
+	}
+
+	if monitor.metrics.pow_completions > 0 {
+		let colour = if monitor.metrics.pow_avg_ms > HIGH_POW_MS { Color::Yellow } else { Color::Blue };
+		push_coloured_metric(
+			&mut items,
+			&"PoW Avg".to_string(),
+			&format!("{:.0}ms (difficulty {:.1})", monitor.metrics.pow_avg_ms, monitor.metrics.pow_avg_difficulty),
+			colour,
+		);
+	}
+
+	if !monitor.metrics.consensus_messages.is_empty() {
+		push_metric(
+			&mut items,
+			&"Consensus".to_string(),
+			&format!("activity {}/min", monitor.metrics.consensus_per_minute()),
+		);
+	}
+
+	push_type_breakdown(&mut items, &"GETS by type".to_string(), &monitor.metrics.gets_by_type);
+	push_type_breakdown(&mut items, &"PUTS by type".to_string(), &monitor.metrics.puts_by_type);
+
+	if let Some(disk_usage_bytes) = monitor.metrics.disk_usage_bytes {
+		push_metric(
+			&mut items,
+			&"Disk Usage".to_string(),
+			&format_size(disk_usage_bytes, 1),
+		);
+		if let Some(per_hour) = monitor.metrics.disk_usage_per_hour() {
+			let sign = if per_hour < 0.0 { "-" } else { "" };
+			push_metric(
+				&mut items,
+				&"Disk Growth".to_string(),
+				&format!("{}{}/h", sign, format_size(per_hour.abs() as u64, 1)),
+			);
+		}
+	}
+
+	if let Some(utilization) = monitor.metrics.quota_utilization() {
+		let colour = if utilization * 100.0 > monitor.metrics.quota_warn_pct as f64 { Color::Yellow } else { Color::Blue };
+		push_coloured_metric(
+			&mut items,
+			&"Quota Used".to_string(),
+			&format!("{} {:.0}%", capacity_bar(1.0 - utilization), utilization * 100.0),
+			colour,
+		);
+	}
+
+	if let Some(memory_usage_mb) = monitor.metrics.memory_usage_mb {
+		push_metric(
+			&mut items,
+			&"Memory".to_string(),
+			&format!("{} MB", memory_usage_mb),
+		);
+	}
+
+	if let Some(max_duration) = monitor.metrics.max_connection_duration() {
+		push_metric(
+			&mut items,
+			&"Conn. Avg".to_string(),
+			&format!("{:.0}s", monitor.metrics.avg_connection_duration_s),
+		);
+		push_metric(
+			&mut items,
+			&"Conn. Max".to_string(),
+			&format!("{}s", max_duration),
+		);
+	}
+
+	if let Some(version) = monitor.metrics.protocol_version {
+		let changes = monitor.metrics.protocol_version_history.len().saturating_sub(1);
+		let value = match changes {
+			0 => format!("v{}", version),
+			_ => format!("v{} ({} changes)", version, changes),
+		};
+		push_metric(&mut items, &"Protocol".to_string(), &value);
+	}
+
+	if monitor.metrics.version_mismatches > 0 {
+		push_metric(
+			&mut items,
+			&"Mismatches".to_string(),
+			&format!("{} ({})", monitor.metrics.version_mismatches, monitor.metrics.mismatched_versions.join(" ")),
+		);
+	}
+
+	if monitor.metrics.elections_count > 0 {
+		push_metric(
+			&mut items,
+			&"Elections".to_string(),
+			&monitor.metrics.elections_count.to_string(),
+		);
+	}
+
 	push_subheading(&mut items, &"".to_string());
+	let mut network_items = node_network_items(monitor);
+	if !network_items.is_empty() {
+		push_subheading(&mut items, &"Network".to_string());
+		items.append(&mut network_items);
+	}
 	// TODO re-instate when available
 	// push_subheading(&mut items, &"Network".to_string());
 	// push_metric(
@@ -134,13 +1017,149 @@ fn draw_node_stats<B: Backend>(f: &mut Frame<B>, area: Rect, monitor: &mut LogMo
 	// 	&monitor.metrics.elders.to_string(),
 	// );
 
-	let heading = format!("Node {:>2} Status", monitor.index + 1);
-	let monitor_widget = List::new(items).block(
-		Block::default()
-			.borders(Borders::ALL)
-			.title(heading.to_string()),
-	);
-	f.render_stateful_widget(monitor_widget, area, &mut monitor.metrics_status.state);
+	if let Some(notification) = monitor.metrics.notifications.last() {
+		push_notification(&mut items, notification);
+	}
+
+	items
+}
+
+///! Network metrics which have been observed, for the node status panel
+fn node_network_items<'a>(monitor: &LogMonitor) -> Vec<ListItem<'a>> {
+	let mut items = Vec::<ListItem>::new();
+	if let Some(latency) = monitor.metrics.avg_beacon_latency_ms() {
+		let colour = if latency > HIGH_BEACON_LATENCY_MS { Color::Yellow } else { Color::Blue };
+		push_coloured_metric(
+			&mut items,
+			&"Beacon Avg".to_string(),
+			&format!("{:.0}ms", latency),
+			colour,
+		);
+	}
+
+	if monitor.metrics.received_bytes_total > 0 || monitor.metrics.sent_bytes_total > 0 {
+		push_metric(
+			&mut items,
+			&"Traffic In".to_string(),
+			&format!("{}/s", format_size(monitor.metrics.bandwidth_in_per_second() as u64, 1)),
+		);
+		push_metric(
+			&mut items,
+			&"Traffic Out".to_string(),
+			&format!("{}/s", format_size(monitor.metrics.bandwidth_out_per_second() as u64, 1)),
+		);
+	}
+
+	if !monitor.metrics.banned_peers.is_empty() {
+		push_metric(&mut items, &"Bans".to_string(), &format!("{} active", monitor.metrics.active_bans()));
+	}
+
+	if let Some(shard_count) = monitor.metrics.shard_count {
+		push_metric(
+			&mut items,
+			&"Shards".to_string(),
+			&format!("{} ({:+})", shard_count, monitor.metrics.shard_count_delta),
+		);
+	}
+
+	if let Some(churn) = monitor.metrics.routing_churn_rate() {
+		let colour = if churn > HIGH_ROUTING_CHURN_PER_MINUTE { Color::Yellow } else { Color::Blue };
+		push_coloured_metric(
+			&mut items,
+			&"Routing".to_string(),
+			&format!(
+				"+{} -{} ({}/min)",
+				monitor.metrics.routing_additions, monitor.metrics.routing_removals, churn
+			),
+			colour,
+		);
+	}
+
+	if monitor.metrics.section_joins > 0 || monitor.metrics.section_leaves > 0 {
+		let ratio = monitor.metrics.churn_ratio();
+		let colour = if ratio > HIGH_CHURN_RATIO { Color::Red } else { Color::Green };
+		push_coloured_metric(
+			&mut items,
+			&"Churn".to_string(),
+			&format!(
+				"{:.2} ({}+ {}-)",
+				ratio, monitor.metrics.section_joins, monitor.metrics.section_leaves
+			),
+			colour,
+		);
+	}
+
+	if monitor.metrics.gossip_sent > 0 || monitor.metrics.gossip_received > 0 {
+		// A fall in gossip following a spike may mean the node has been partitioned
+		let colour = match monitor.metrics.trend_direction("GOSSIP", "1 minute columns") {
+			TrendDirection::Falling => Color::Yellow,
+			_ => Color::Blue,
+		};
+		push_coloured_metric(
+			&mut items,
+			&"Gossip".to_string(),
+			&format!("{}/min", monitor.metrics.gossip_per_minute()),
+			colour,
+		);
+	}
+	items
+}
+
+// How long a new notification flashes for
+const NOTIFICATION_FLASH_SECONDS: i64 = 5;
+
+fn push_notification(items: &mut Vec<ListItem>, notification: &Notification) {
+	let colour = match notification.level {
+		NotificationLevel::Critical => Color::Red,
+		NotificationLevel::Warning => Color::Magenta,
+		NotificationLevel::Info => Color::Green,
+	};
+	let mut style = Style::default().fg(colour).add_modifier(Modifier::BOLD);
+	if (Utc::now() - notification.raised).num_seconds() < NOTIFICATION_FLASH_SECONDS {
+		style = style.add_modifier(Modifier::SLOW_BLINK);
+	}
+	items.push(ListItem::new(vec![Spans::from(notification.message.clone())]).style(style));
+}
+
+// Width of the stacked bar, to fit beside a metric name in the status panel
+const TYPE_BREAKDOWN_WIDTH: u64 = 24;
+const TYPE_BREAKDOWN_COLOURS: [Color; 4] = [Color::Cyan, Color::Magenta, Color::Green, Color::Yellow];
+
+///! Show counts by data type as a stacked bar followed by a key to its colours
+fn push_type_breakdown(items: &mut Vec<ListItem>, metric: &String, counts: &HashMap<String, u64>) {
+	let total: u64 = counts.values().sum();
+	if total == 0 {
+		return;
+	}
+
+	let mut data_types: Vec<(&String, &u64)> = counts.iter().collect();
+	data_types.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+
+	let mut bar = vec![Span::styled(
+		format!("{:<12}: ", metric),
+		Style::default().fg(Color::Blue),
+	)];
+	let mut used = 0;
+	for (i, (_, count)) in data_types.iter().enumerate() {
+		let colour = TYPE_BREAKDOWN_COLOURS[i % TYPE_BREAKDOWN_COLOURS.len()];
+		let width = if i == data_types.len() - 1 {
+			TYPE_BREAKDOWN_WIDTH - used
+		} else {
+			**count * TYPE_BREAKDOWN_WIDTH / total
+		};
+		used += width;
+		bar.push(Span::styled(
+			"█".repeat(width as usize),
+			Style::default().fg(colour),
+		));
+	}
+	items.push(ListItem::new(vec![Spans::from(bar)]));
+
+	for (i, (data_type, count)) in data_types.iter().enumerate() {
+		let colour = TYPE_BREAKDOWN_COLOURS[i % TYPE_BREAKDOWN_COLOURS.len()];
+		let s = format!("  {:<24} {:>10}", data_type, count);
+		items.push(ListItem::new(vec![Spans::from(s)]).style(Style::default().fg(colour)));
+	}
 }
 
 fn push_subheading(items: &mut Vec<ListItem>, subheading: &String) {
@@ -151,10 +1170,14 @@ fn push_subheading(items: &mut Vec<ListItem>, subheading: &String) {
 }
 
 fn push_metric(items: &mut Vec<ListItem>, metric: &String, value: &String) {
+	push_coloured_metric(items, metric, value, Color::Blue);
+}
+
+fn push_coloured_metric(items: &mut Vec<ListItem>, metric: &String, value: &String, colour: Color) {
 	let s = format!("{:<12}: {:>12}", metric, value);
 	items.push(
 		ListItem::new(vec![Spans::from(s.clone())])
-			.style(Style::default().fg(Color::Blue)),
+			.style(Style::default().fg(colour)),
 	);
 }
 
@@ -304,9 +1327,10 @@ fn draw_timeline<B: Backend>(
 		Some((name, _)) => name,
 	};
 
+	let title = format!("Timeline - {}", active_timeline_name);
 	let window_widget = Block::default()
 		.borders(Borders::ALL)
-		.title(format!("Timeline - {}", active_timeline_name).to_string());
+		.title(title);
 	f.render_widget(window_widget, area);
 
 	// For debugging the bucket state
@@ -352,7 +1376,8 @@ fn draw_timeline<B: Backend>(
 		.puts_timeline
 		.get_bucket_set(active_timeline_name)
 	{
-		draw_sparkline(f, chunks[0], &bucket_set.buckets(), &"PUTS", Color::Yellow);
+		let annotations = monitor.metrics.event_annotations(bucket_set);
+		draw_sparkline(f, chunks[0], &bucket_set, &annotations, &"PUTS", Color::Yellow);
 	};
 
 	if let Some(bucket_set) = monitor
@@ -360,7 +1385,8 @@ fn draw_timeline<B: Backend>(
 		.gets_timeline
 		.get_bucket_set(active_timeline_name)
 	{
-		draw_sparkline(f, chunks[1], &bucket_set.buckets(), &"GETS", Color::Green);
+		let annotations = monitor.metrics.event_annotations(bucket_set);
+		draw_sparkline(f, chunks[1], &bucket_set, &annotations, &"GETS", Color::Green);
 	};
 
 	if let Some(bucket_set) = monitor
@@ -368,30 +1394,63 @@ fn draw_timeline<B: Backend>(
 		.errors_timeline
 		.get_bucket_set(active_timeline_name)
 	{
-		draw_sparkline(f, chunks[2], &bucket_set.buckets(), &"ERRORS", Color::Red);
+		let annotations = monitor.metrics.event_annotations(bucket_set);
+		draw_sparkline(f, chunks[2], &bucket_set, &annotations, &"ERRORS", Color::Red);
 	};
 }
 
+const CAPACITY_BAR_WIDTH: usize = 8;
+
+// How much of the timeline history has accumulated, as a small bar
+fn capacity_bar(capacity_remaining: f64) -> String {
+	let filled = ((1.0 - capacity_remaining) * CAPACITY_BAR_WIDTH as f64).round() as usize;
+	let filled = filled.min(CAPACITY_BAR_WIDTH);
+	format!("{}{}", "▰".repeat(filled), "▱".repeat(CAPACITY_BAR_WIDTH - filled))
+}
+
+// Used for the dashed marker at the right of each sparkline
+const SPARKLINE_PREDICTION: PredictionMethod = PredictionMethod::MovingAverage(10);
+
 fn draw_sparkline<B: Backend>(
 	f: &mut Frame<B>,
 	area: Rect,
-	buckets: &Vec<u64>,
+	bucket_set: &BucketSet,
+	annotations: &[Option<String>],
 	title: &str,
 	fg_colour: tui::style::Color,
 	) {
 
+		let width = area.width.saturating_sub(1); // Leave room for the prediction marker
+		let title = format!(
+			"{} {}  history {}",
+			title,
+			density_indicator(bucket_set.event_density()),
+			capacity_bar(bucket_set.capacity_remaining())
+		);
 		let sparkline = Sparkline2::default()
 		.block(Block::default().title(title))
-		.data(buckets_right_justify(
-			&buckets,
-			area.width,
-		))
+		.data(buckets_right_justify(&bucket_set.buckets(), width))
+		.annotations(buckets_right_justify(annotations, width))
+		.marker(bucket_set.predict_next(SPARKLINE_PREDICTION))
 		.style(Style::default().fg(fg_colour));
 	f.render_widget(sparkline, area);
 }
 
+// One character showing how busy a timeline is (see BucketSet::event_density())
+fn density_indicator(density: f64) -> &'static str {
+	if density < 0.25 {
+		"░"
+	} else if density < 0.5 {
+		"▒"
+	} else if density < 0.75 {
+		"▓"
+	} else {
+		"█"
+	}
+}
+
 // Right justify and truncate (left) a set of buckets to width
-fn buckets_right_justify(buckets: &Vec<u64>, width: u16) -> &[u64] {
+fn buckets_right_justify<T>(buckets: &[T], width: u16) -> &[T] {
 	let width = width as usize;
 	if width < buckets.len() {
 		return &buckets[buckets.len() - width..];
@@ -406,6 +1465,7 @@ fn draw_bottom_panel<B: Backend>(
 	dash_state: &mut DashState,
 	logfile: &String,
 	monitor: &mut LogMonitor,
+	monitors_status: &str,
 ) {
 	if dash_state.debug_window {
 		// Vertical split:
@@ -419,10 +1479,10 @@ fn draw_bottom_panel<B: Backend>(
 			.constraints(constraints.as_ref())
 			.split(area);
 
-		draw_logfile(f, chunks[0], &logfile, monitor);
+		draw_logfile(f, chunks[0], &logfile, monitor, monitors_status, dash_state.inspector_mode);
 		draw_debug_window(f, chunks[1], dash_state);
 	} else {
-		draw_logfile(f, area, &logfile, monitor);
+		draw_logfile(f, area, &logfile, monitor, monitors_status, dash_state.inspector_mode);
 	}
 }
 
@@ -431,6 +1491,8 @@ pub fn draw_logfile<B: Backend>(
 	area: Rect,
 	logfile: &String,
 	monitor: &mut LogMonitor,
+	monitors_status: &str,
+	inspector_mode: bool,
 ) {
 	let highlight_style = match monitor.has_focus {
 		true => Style::default()
@@ -439,17 +1501,64 @@ pub fn draw_logfile<B: Backend>(
 		false => Style::default().add_modifier(Modifier::BOLD),
 	};
 
+	// In inspector mode each line of the focused logfile is followed by what LogEntry::decode() made of it
+	let inspect = inspector_mode && monitor.has_focus;
+
+	// Only the visible lines are rendered, scrolling no more than needed to show the selection
+	let mut visible_lines = area.height.saturating_sub(2) as usize; // Inside the borders
+	if inspect {
+		visible_lines /= 2;
+	}
+	let selected = monitor.content.state.selected();
+	if let Some(selected) = selected {
+		if selected < monitor.content_offset {
+			monitor.content_offset = selected;
+		} else if selected >= monitor.content_offset + visible_lines {
+			monitor.content_offset = (selected + 1).saturating_sub(visible_lines);
+		}
+	}
+	monitor.content_offset = monitor
+		.content_offset
+		.min(monitor.content.items.len().saturating_sub(visible_lines));
+
 	let items: Vec<ListItem> = monitor
-		.content
-		.items
+		.content_window(monitor.content_offset, visible_lines)
 		.iter()
-		.map(|s| {
-			ListItem::new(vec![Spans::from(s.clone())])
-				.style(Style::default().fg(Color::Black).bg(Color::White))
+		.enumerate()
+		.map(|(i, s)| {
+			let line = match monitor.repetition_count(monitor.content_offset + i) {
+				1 => s.clone(),
+				count => format!("{} [×{}]", s, count),
+			};
+			let mut spans = if monitor.ansi_log {
+				vec![ansi_to_spans(&line)]
+			} else {
+				vec![Spans::from(strip_ansi(&line))]
+			};
+			if inspect {
+				let parser_output = LogEntry::decode(s).map_or(String::from("not decoded"), |entry| entry.parser_output);
+				spans.push(Spans::from(Span::styled(
+					format!("  ↳ {}", parser_output),
+					Style::default().add_modifier(Modifier::DIM),
+				)));
+			}
+			ListItem::new(spans)
+				.style(monitor.apply_highlight_rules(s))
 		})
 		.collect();
 
-	let node_log_title = format!("Node Log ({})", logfile);
+	let mut window_state = ListState::default();
+	window_state.select(selected.and_then(|selected| selected.checked_sub(monitor.content_offset)));
+
+	let log_rate = monitor.displayed_log_rate();
+	let mut node_log_title = if monitor.is_aggregate {
+		format!("Aggregate ({}) {:.1} lines/s", logfile, log_rate)
+	} else {
+		format!("Node Log ({}) {:.1} lines/s", logfile, log_rate)
+	};
+	if !monitors_status.is_empty() {
+		node_log_title.push_str(&format!(" [{}]", monitors_status));
+	}
 
 	let logfile_widget = List::new(items)
 		.block(
@@ -459,7 +1568,7 @@ pub fn draw_logfile<B: Backend>(
 		)
 		.highlight_style(highlight_style);
 
-	f.render_stateful_widget(logfile_widget, area, &mut monitor.content.state);
+	f.render_stateful_widget(logfile_widget, area, &mut window_state);
 }
 
 fn draw_debug_window<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &mut DashState) {
@@ -494,3 +1603,19 @@ fn draw_debug_window<B: Backend>(f: &mut Frame<B>, area: Rect, dash_state: &mut
 		&mut dash_state.debug_window_list.state,
 	);
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn ansi_codes_become_styles() {
+		let line = "\x1b[1;31mERROR\x1b[0m failed";
+		assert_eq!(strip_ansi(line), "ERROR failed");
+		let spans = ansi_to_spans(line);
+		assert_eq!(spans.0.len(), 2);
+		assert_eq!(spans.0[0].content, "ERROR");
+		assert_eq!(spans.0[0].style, Style::default().fg(Color::Red).add_modifier(Modifier::BOLD));
+		assert_eq!(spans.0[1].style, Style::default());
+	}
+}
@@ -5,6 +5,10 @@ use std::collections::HashMap;
 
 use tui::{
 	backend::Backend,
+	layout::{Constraint, Direction, Layout},
+	style::{Color, Style},
+	text::Spans,
+	widgets::{Block, Borders, List, ListItem},
 	Frame
 };
 
@@ -17,6 +21,9 @@ pub fn draw_dashboard<B: Backend>(
 		DashViewMain::DashSummary => {}
 		DashViewMain::DashNode => {}
 		DashViewMain::DashDebug => draw_debug_dashboard(f, dash_state, monitors),
+		DashViewMain::DashHealth => {}
+		DashViewMain::DashSearch => {}
+		DashViewMain::DashNetwork => {}
 	}
 }
 
@@ -27,9 +34,91 @@ fn draw_debug_dashboard<B: Backend>(
 	_dash_state: &DashState,
 	monitors: &mut HashMap<String, LogMonitor>,
 ) {
+	// Code section and task timings parsed from the logfile, if any, are shown on the right
+	let mut tables = Vec::new();
+	if let Some(monitor) = monitors
+		.values()
+		.find(|monitor| !monitor.is_debug_dashboard_log && !monitor.metrics.section_latencies.is_empty())
+	{
+		tables.push(("Section Latencies (µs)", section_latency_items(monitor)));
+	}
+	if let Some(monitor) = monitors
+		.values()
+		.find(|monitor| !monitor.is_debug_dashboard_log && !monitor.metrics.task_durations.is_empty())
+	{
+		tables.push(("Slowest Tasks (ms)", slowest_task_items(monitor)));
+	}
+
+	let mut area = f.size();
+	if !tables.is_empty() {
+		let chunks = Layout::default()
+			.direction(Direction::Horizontal)
+			.constraints([Constraint::Min(0), Constraint::Length(48)].as_ref())
+			.split(area);
+		area = chunks[0];
+
+		let constraints = vec![Constraint::Ratio(1, tables.len() as u32); tables.len()];
+		let rows = Layout::default()
+			.direction(Direction::Vertical)
+			.constraints(constraints.as_ref())
+			.split(chunks[1]);
+		for ((title, items), row) in tables.into_iter().zip(rows) {
+			let widget = List::new(items).block(Block::default().borders(Borders::ALL).title(title));
+			f.render_widget(widget, row);
+		}
+	}
+
 	for (logfile, monitor) in monitors.iter_mut() {
 		if monitor.is_debug_dashboard_log {
-			draw_logfile(f, f.size(), logfile, monitor);
+			draw_logfile(f, area, logfile, monitor, "", false);
 		}
 	}
 }
+
+// A row per task, slowest P95 first
+fn slowest_task_items<'a>(monitor: &LogMonitor) -> Vec<ListItem<'a>> {
+	let metrics = &monitor.metrics;
+	let mut rows: Vec<(&String, u64, u64)> = metrics
+		.task_durations
+		.keys()
+		.map(|name| (name, metrics.task_p95(name).unwrap_or(0), metrics.task_p50(name).unwrap_or(0)))
+		.collect();
+	rows.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+	let mut items = vec![ListItem::new(vec![Spans::from(format!(
+		"{:<24} {:>9} {:>9}",
+		"Task", "P95", "P50"
+	))])
+	.style(Style::default().fg(Color::Yellow))];
+	for (name, p95, p50) in rows {
+		items.push(ListItem::new(vec![Spans::from(format!(
+			"{:<24} {:>9} {:>9}",
+			name, p95, p50
+		))]));
+	}
+	items
+}
+
+// A row per code section, slowest P99 first
+fn section_latency_items<'a>(monitor: &LogMonitor) -> Vec<ListItem<'a>> {
+	let metrics = &monitor.metrics;
+	let mut rows: Vec<(&String, u64, usize)> = metrics
+		.section_latencies
+		.iter()
+		.map(|(name, timings)| (name, metrics.section_p99(name).unwrap_or(0), timings.len()))
+		.collect();
+	rows.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+	let mut items = vec![ListItem::new(vec![Spans::from(format!(
+		"{:<24} {:>10} {:>8}",
+		"Section", "P99", "Samples"
+	))])
+	.style(Style::default().fg(Color::Yellow))];
+	for (name, p99, samples) in rows {
+		items.push(ListItem::new(vec![Spans::from(format!(
+			"{:<24} {:>10} {:>8}",
+			name, p99, samples
+		))]));
+	}
+	items
+}
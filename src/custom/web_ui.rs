@@ -0,0 +1,100 @@
+///! Read-only web dashboard (see --web-ui-port)
+///!
+///! The page at / polls /api/metrics, which returns the MetricsSnapshot of each
+///! monitored logfile as JSON. App::export_metrics() updates the snapshots on each tick.
+///! Served with warp 0.2, the last warp release for tokio 0.2.
+
+use std::io::{Error, ErrorKind};
+use std::net::Ipv4Addr;
+use std::sync::{Arc, Mutex};
+use warp::Filter;
+
+use crate::custom::snapshot::MetricsSnapshot;
+
+pub type SharedSnapshots = Arc<Mutex<Vec<MetricsSnapshot>>>;
+
+///! Serve the dashboard at http://0.0.0.0:<port>/ until the app exits
+pub async fn serve_web_ui(port: u16, snapshots: SharedSnapshots) -> Result<(), Error> {
+	let page = warp::path::end().map(|| warp::reply::html(DASHBOARD_HTML));
+	let metrics = warp::path!("api" / "metrics")
+		.map(move || warp::reply::json(&*snapshots.lock().unwrap()));
+	let routes = warp::get().and(page.or(metrics));
+
+	let (_address, server) = warp::serve(routes)
+		.try_bind_ephemeral((Ipv4Addr::UNSPECIFIED, port))
+		.map_err(|e| Error::new(ErrorKind::Other, format!("web UI: {}", e)))?;
+	server.await;
+	Ok(())
+}
+
+static DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>vdash</title>
+<script src="https://cdn.jsdelivr.net/npm/chart.js@2.9.4/dist/Chart.min.js"></script>
+<style>
+	body { font-family: monospace; background: #111; color: #ddd; }
+	.node { border: 1px solid #444; margin: 1em 0; padding: 0.5em; }
+	.chart { height: 120px; }
+</style>
+</head>
+<body>
+<h1>vdash</h1>
+<div id="nodes"></div>
+<script>
+const COLOURS = { PUTS: "#cc0", GETS: "#0c0", ERRORS: "#c00" };
+const charts = {};
+
+function nodeElement(snapshot, index) {
+	let element = document.getElementById("node-" + index);
+	if (!element) {
+		element = document.createElement("div");
+		element.id = "node-" + index;
+		element.className = "node";
+		element.innerHTML = '<pre class="stats"></pre><div class="chart"><canvas></canvas></div>';
+		document.getElementById("nodes").appendChild(element);
+		charts[index] = new Chart(element.querySelector("canvas"), {
+			type: "line",
+			data: { labels: [], datasets: [] },
+			options: { animation: false, maintainAspectRatio: false, elements: { point: { radius: 0 } } },
+		});
+	}
+	return element;
+}
+
+function render(snapshots) {
+	snapshots.forEach((snapshot, index) => {
+		const element = nodeElement(snapshot, index);
+		element.querySelector(".stats").textContent =
+			snapshot.logfile + (snapshot.tags.length ? " [" + snapshot.tags.join(", ") + "]" : "") + "\n" +
+			"Health " + snapshot.health_score + "%  Age " + snapshot.node_age +
+			"  GETS " + snapshot.gets + "  PUTS " + snapshot.puts + "  ERRORS " + snapshot.errors +
+			"  Elders " + snapshot.elders + "  Adults " + snapshot.adults;
+
+		const chart = charts[index];
+		const names = Object.keys(COLOURS).filter(name => snapshot.timelines[name]);
+		const length = Math.max(0, ...names.map(name => snapshot.timelines[name].length));
+		chart.data.labels = Array.from({ length }, (_, i) => i - length + 1);
+		chart.data.datasets = names.map(name => ({
+			label: name + " (" + snapshot.timeline_scale + ")",
+			data: snapshot.timelines[name],
+			borderColor: COLOURS[name],
+			fill: false,
+		}));
+		chart.update();
+	});
+}
+
+function poll() {
+	fetch("/api/metrics")
+		.then(response => response.json())
+		.then(render)
+		.catch(e => console.log(e));
+}
+poll();
+setInterval(poll, 1000);
+</script>
+</body>
+</html>
+"##;
@@ -1,7 +1,7 @@
 #![allow(dead_code)]
 use std::io;
 use std::sync::{
-	atomic::{AtomicBool, Ordering},
+	atomic::{AtomicBool, AtomicU64, Ordering},
 	Arc,
 };
 use std::thread;
@@ -46,6 +46,13 @@ impl Events {
 	}
 
 	pub fn with_config(config: Config) -> Events {
+		let tick_rate_ms = Arc::new(AtomicU64::new(config.tick_rate.as_millis() as u64));
+		Events::with_tick_rate(config, tick_rate_ms)
+	}
+
+	/// Like `with_config()`, but ticking every `tick_rate_ms` milliseconds, which
+	/// can be changed while running
+	pub fn with_tick_rate(config: Config, tick_rate_ms: Arc<AtomicU64>) -> Events {
 		let (tx, rx) = mpsc::unbounded_channel();
 		let ignore_exit_key = Arc::new(AtomicBool::new(false));
 		let input_handle = {
@@ -71,7 +78,7 @@ impl Events {
 				if tx.send(Event::Tick).is_err() {
 					break;
 				}
-				thread::sleep(config.tick_rate);
+				thread::sleep(Duration::from_millis(tick_rate_ms.load(Ordering::Relaxed)));
 			})
 		};
 		Events {
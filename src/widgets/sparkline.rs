@@ -24,6 +24,10 @@ pub struct Sparkline2<'a> {
 	max: Option<u64>,
 	/// A set of bar symbols used to represent the give data
 	bar_set: symbols::bar::Set,
+	/// Optional value shown as a dashed bar after the data (e.g. a prediction)
+	marker: Option<u64>,
+	/// Event labels aligned with data, shown as vertical lines above the bars
+	annotations: &'a [Option<String>],
 }
 
 impl<'a> Default for Sparkline2<'a> {
@@ -34,6 +38,8 @@ impl<'a> Default for Sparkline2<'a> {
 			data: &[],
 			max: None,
 			bar_set: symbols::bar::NINE_LEVELS,
+			marker: None,
+			annotations: &[],
 		}
 	}
 }
@@ -63,6 +69,16 @@ impl<'a> Sparkline2<'a> {
 		self.bar_set = bar_set;
 		self
 	}
+
+	pub fn marker(mut self, marker: u64) -> Sparkline2<'a> {
+		self.marker = Some(marker);
+		self
+	}
+
+	pub fn annotations(mut self, annotations: &'a [Option<String>]) -> Sparkline2<'a> {
+		self.annotations = annotations;
+		self
+	}
 }
 
 impl<'a> Widget for Sparkline2<'a> {
@@ -76,15 +92,19 @@ impl<'a> Widget for Sparkline2<'a> {
 			None => area,
 		};
 
-		if spark_area.height < 1 {
+		if spark_area.height < 1 || spark_area.width < 1 {
 			return;
 		}
 
 		let max = match self.max {
 			Some(v) => v,
-			None => *self.data.iter().max().unwrap_or(&1u64),
+			None => *self.data.iter().chain(self.marker.iter()).max().unwrap_or(&1u64),
+		};
+		let data_width = match self.marker {
+			Some(_) => spark_area.width - 1, // Room for the marker
+			None => spark_area.width,
 		};
-		let max_index = min(spark_area.width as usize, self.data.len());
+		let max_index = min(data_width as usize, self.data.len());
 		let mut data = self
 			.data
 			.iter()
@@ -121,6 +141,31 @@ impl<'a> Widget for Sparkline2<'a> {
 				}
 			}
 		}
+
+		for (i, annotation) in self.annotations.iter().take(max_index).enumerate() {
+			if annotation.is_some() {
+				for j in 0..spark_area.height {
+					let cell = buf.get_mut(spark_area.left() + i as u16, spark_area.top() + j);
+					if cell.symbol == self.bar_set.empty {
+						cell.set_symbol("│").set_style(self.style);
+					}
+				}
+			}
+		}
+
+		if let Some(marker) = self.marker {
+			let marker_eighths = if max != 0 {
+				marker * u64::from(spark_area.height) * 8 / max
+			} else {
+				0
+			};
+			let marker_rows = ((marker_eighths + 7) / 8) as u16;
+			for j in 0..marker_rows.min(spark_area.height) {
+				buf.get_mut(spark_area.left() + max_index as u16, spark_area.bottom() - 1 - j)
+					.set_symbol("╎")
+					.set_style(self.style);
+			}
+		}
 	}
 }
 
@@ -143,4 +188,24 @@ mod tests {
 		let mut buffer = Buffer::empty(area);
 		widget.render(area, &mut buffer);
 	}
+
+	#[test]
+	fn it_draws_marker_after_data() {
+		let widget = Sparkline2::default().data(&[1, 2]).marker(2);
+		let area = Rect::new(0, 0, 3, 1);
+		let mut buffer = Buffer::empty(area);
+		widget.render(area, &mut buffer);
+		assert_eq!(buffer.get(2, 0).symbol, "╎");
+	}
+
+	#[test]
+	fn it_draws_annotation_above_bar() {
+		let annotations = [None, Some("restart".to_string())];
+		let widget = Sparkline2::default().data(&[2, 0]).max(2).annotations(&annotations);
+		let area = Rect::new(0, 0, 2, 2);
+		let mut buffer = Buffer::empty(area);
+		widget.render(area, &mut buffer);
+		assert_eq!(buffer.get(1, 0).symbol, "│");
+		assert_eq!(buffer.get(0, 0).symbol, symbols::bar::FULL);
+	}
 }